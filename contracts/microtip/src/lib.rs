@@ -11,10 +11,77 @@
 
 // Import necessary Soroban SDK modules
 use soroban_sdk::{
-    contract, contractimpl, contracttype, Address, Env, String, Symbol, Vec, Map,
-    token::Client as TokenClient, TryFromVal, FromVal,
+    contract, contractimpl, contracttype, symbol_short, Address, BytesN, Env, IntoVal, Map, String,
+    Symbol, Val, Vec,
+    token::Client as TokenClient, xdr::ToXdr,
 };
 
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Version of this contract code; bump whenever the storage layout changes
+/// Version 1 kept all tips in a single instance vector; version 2 stores
+/// each tip under its own id
+const VERSION: u32 = 2;
+
+/// Instance key of the version 1 tip vector, present until migration finishes
+const LEGACY_TIPS_KEY: &str = "tip";
+
+/// Bit set on every hash-derived tip id, keeping them apart from counter ids
+const HASHED_ID_FLAG: u64 = 1 << 63;
+
+/// Maximum number of entries returned by a single paginated query
+const MAX_PAGE_SIZE: u32 = 50;
+
+/// Message length limit used until the admin configures one
+const DEFAULT_MAX_MESSAGE_LEN: u32 = 256;
+
+/// Hard ceiling on the configurable message length, to bound storage
+const MAX_MESSAGE_LEN_CEILING: u32 = 1024;
+
+/// Most recipients accepted by one batch or split tip, used until the
+/// admin configures a different limit
+const MAX_BATCH: u32 = 50;
+
+/// Hard ceiling on the configurable batch size, to bound resource use
+const MAX_BATCH_CEILING: u32 = 200;
+
+/// Decimals assumed for tokens that do not report them (the Stellar standard)
+const DEFAULT_TOKEN_DECIMALS: u32 = 7;
+
+/// Basis points in 100%, used for all percentage-based fees
+const BPS_DENOMINATOR: i128 = 10_000;
+
+/// Longest accepted user handle, in bytes
+const MAX_HANDLE_LEN: u32 = 32;
+
+/// Seconds after sending during which a tip's message may be edited,
+/// used until the admin configures a different grace period
+const DEFAULT_EDIT_GRACE_SECS: u64 = 300;
+
+/// Seconds after sending during which a sender may unsend their latest
+/// tip, used until the admin configures a different window
+const DEFAULT_UNSEND_WINDOW_SECS: u64 = 60;
+
+/// Length of a day in seconds, the unit of tipping streaks
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Most tokens tracked by the token volume leaderboard
+const MAX_TRACKED_TOKENS: u32 = 50;
+
+/// Most recipients ranked on each token's leaderboard
+const MAX_LEADERBOARD_SIZE: u32 = 50;
+
+/// Most sender-recipient pairs kept in the top pairs ranking
+const MAX_TRACKED_PAIRS: u32 = 50;
+
+/// Highest withdrawal fee the admin may configure, in basis points (10%)
+const MAX_WITHDRAW_FEE_BPS: u32 = 1_000;
+
+/// Sender reported for anonymous tips (the all-zero Stellar account)
+const ANONYMOUS_SENDER: &str = "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF";
+
 // ============================================================================
 // DATA STRUCTURES
 // ============================================================================
@@ -30,7 +97,35 @@ pub struct Tip {
     to: Address,
     /// Amount of the tip in the smallest unit of the token
     amount: i128,
-    /// Message attached to the tip (optional, up to 256 characters)
+    /// Message attached to the tip (optional, up to the configured maximum length)
+    message: String,
+    /// Timestamp (in seconds) when the tip was sent
+    timestamp: u64,
+    /// Token contract address used for this tip
+    token: Address,
+    /// Whether the sender is hidden from public queries and events
+    anonymous: bool,
+    /// Address that paid the platform fee on the sender's behalf, if any
+    sponsor: Option<Address>,
+    /// Bookkeeping tag chosen by the sender, or `none` for untagged tips
+    category: Symbol,
+    /// Whether the tip may appear in public feeds
+    public: bool,
+}
+
+/// A tip as stored by version 1 of the contract, before tips gained
+/// anonymity and sponsorship fields
+/// Only used to read state written by that version during migration
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct LegacyTip {
+    /// Address of the person sending the tip
+    from: Address,
+    /// Address of the tip recipient
+    to: Address,
+    /// Amount of the tip in the smallest unit of the token
+    amount: i128,
+    /// Message attached to the tip
     message: String,
     /// Timestamp (in seconds) when the tip was sent
     timestamp: u64,
@@ -38,6 +133,56 @@ pub struct Tip {
     token: Address,
 }
 
+/// Outcome of a tip, for callers that want to skip a follow-up query
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SendResult {
+    /// Id assigned to the tip
+    tip_id: u64,
+    /// Recipient's available balance in the tip's token after crediting it
+    recipient_available: i128,
+}
+
+/// A tip together with its refund and acknowledgement state
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct TipStatus {
+    /// The tip, in its public form
+    tip: Tip,
+    /// Whether the tip has been fully refunded (or reclaimed or unsent)
+    refunded: bool,
+    /// Amount returned to the sender so far
+    refunded_amount: i128,
+    /// Recipient's acknowledgement reaction, if any
+    ack: Option<Symbol>,
+}
+
+/// Portable proof of a tip, for display and verification off-chain
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct TipReceipt {
+    /// Id of the tip
+    tip_id: u64,
+    /// Sender of the tip (the sentinel address for anonymous tips)
+    from: Address,
+    /// Recipient of the tip
+    to: Address,
+    /// Amount of the tip in the smallest unit of the token
+    amount: i128,
+    /// Message attached to the tip
+    message: String,
+    /// Timestamp (in seconds) when the tip was sent
+    timestamp: u64,
+    /// Token contract address used for the tip
+    token: Address,
+    /// Address of the contract that recorded the tip
+    contract: Address,
+    /// Version of the contract code that issued the receipt
+    version: u32,
+    /// Receipt hash of the tip, as returned by `get_tip_hash`
+    hash: BytesN<32>,
+}
+
 /// Represents the balance information for a user
 /// Tracks accumulated tips and withdrawal information
 #[contracttype]
@@ -68,6 +213,386 @@ pub struct UserProfile {
     total_received: i128,
     /// Timestamp of the user's first interaction with the contract
     first_interaction: u64,
+    /// Timestamp of the user's most recent tip sent or received
+    last_interaction: u64,
+    /// Number of consecutive days on which the user has sent a tip
+    streak: u32,
+    /// Timestamp of the tip that last extended or restarted the streak
+    streak_updated: u64,
+}
+
+/// Platform fee configuration
+/// The fee is charged in its own token on top of the tip itself
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct FeeConfig {
+    /// Token contract address the fee is paid in
+    fee_token: Address,
+    /// Flat fee amount charged per tip, in the smallest unit of `fee_token`
+    fee_flat: i128,
+}
+
+/// A single payout of a user's tip balance
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Withdrawal {
+    /// Address whose balance was withdrawn
+    user: Address,
+    /// Token contract address withdrawn
+    token: Address,
+    /// Amount withdrawn, in the smallest unit of the token
+    amount: i128,
+    /// Timestamp (in seconds) of the withdrawal
+    timestamp: u64,
+    /// Address that received the tokens
+    destination: Address,
+}
+
+/// One page of the global withdrawal ledger
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct WithdrawalPage {
+    /// Withdrawals in this page, oldest first
+    withdrawals: Vec<Withdrawal>,
+    /// Cursor to pass as `after_id` for the next page; None at the end
+    next: Option<u64>,
+}
+
+/// Platform-wide metrics for dashboards, gathered in one call
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct PlatformStats {
+    /// Number of tips ever sent
+    total_tips: u64,
+    /// Number of distinct addresses that have sent a tip
+    unique_senders: u32,
+    /// Number of distinct addresses that have received a tip
+    unique_recipients: u32,
+    /// Number of tokens on the allowlist (zero means all tokens are accepted)
+    supported_tokens: u32,
+}
+
+/// A record of one admin action, kept for compliance review
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    /// Admin that performed the action
+    actor: Address,
+    /// Name of the action, e.g. `freeze`
+    action: Symbol,
+    /// User, token or contract the action applied to
+    target: Address,
+    /// Reason code supplied with the action
+    reason: Symbol,
+    /// Timestamp (in seconds) of the action
+    timestamp: u64,
+}
+
+/// Input limits a client can validate against before submitting
+/// Bundled so forms can be checked with a single call
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Limits {
+    /// Smallest accepted tip amount
+    min_tip_amount: i128,
+    /// Longest accepted tip message, in bytes
+    max_message_len: u32,
+    /// Smallest tip amount that may carry a non-empty message
+    message_min_amount: i128,
+    /// Smallest accepted withdrawal amount
+    min_withdrawal_amount: i128,
+    /// Most recipients accepted by one batch or split tip
+    max_batch: u32,
+}
+
+/// A sponsor's pledge to match tips to a recipient, funded up front
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct MatchPledge {
+    /// Address that funded the pledge
+    sponsor: Address,
+    /// Part of the pledged cap not yet paid out as matches
+    remaining: i128,
+}
+
+/// A funded tip held back until an authorizer confirms its condition
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct ConditionalTip {
+    /// The tip to credit on release; its timestamp is reset at that point
+    tip: Tip,
+    /// Key identifying the off-chain condition, e.g. `video_posted`
+    condition: Symbol,
+}
+
+/// A recipient's personal fundraising goal in one token
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Goal {
+    /// Total received amount that completes the goal
+    target: i128,
+    /// Whether the goal has been reached
+    completed: bool,
+}
+
+/// A shared tip jar whose funds are split evenly among its members
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Pool {
+    /// Addresses sharing the pool, in claim-priority order
+    members: Vec<Address>,
+    /// Token the pool accepts
+    token: Address,
+    /// Total ever tipped to the pool
+    total_received: i128,
+}
+
+/// A user's request to be notified when a balance reaches a target
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct BalanceAlert {
+    /// Available balance that triggers the alert
+    threshold: i128,
+    /// Whether the alert has already fired since it was last set
+    triggered: bool,
+}
+
+/// A sensitive admin action that needs approval from several admins
+#[contracttype]
+#[derive(Clone, Debug)]
+pub enum Action {
+    /// Replace the contract code with the uploaded WASM of this hash
+    Upgrade(BytesN<32>),
+    /// Pay out all accrued fees in a token: (token, destination)
+    WithdrawFees(Address, Address),
+    /// Replace the approver set and threshold: (admins, threshold)
+    SetAdmins(Vec<Address>, u32),
+}
+
+/// A proposed admin action and the admins that have approved it
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Proposal {
+    /// The action to perform once approved
+    action: Action,
+    /// Distinct admins that have approved, proposer first
+    approvals: Vec<Address>,
+    /// Whether the action has been carried out
+    executed: bool,
+}
+
+// Storage keys are split across several enums because a contract type
+// enum is limited to 50 cases. Each key is encoded by its variant name, so
+// every variant name must stay unique across the enums
+
+/// Keys for platform-wide configuration and contract state flags
+#[contracttype]
+#[derive(Clone)]
+pub enum ConfigKey {
+    /// Flag set once `init` has been called
+    Initialized,
+    /// Address allowed to manage platform configuration
+    Admin,
+    /// Destinations a token may be withdrawn to; absent means unrestricted
+    WithdrawAllowlist(Address),
+    /// Marks a token as approved for tipping
+    AllowedToken(Address),
+    /// Number of approved tokens; zero means every token is accepted
+    AllowedTokenCount,
+    /// Platform fee configuration; absent means no fee is charged
+    FeeConfig,
+    /// Share of a refunded amount kept as a reversal fee, in basis points
+    RefundFeeBps,
+    /// Maximum tip message length in bytes
+    MaxMessageLen,
+    /// Reentrancy guard, set while a tip or withdrawal is moving funds
+    Lock,
+    /// Smallest tip amount allowed to carry a message
+    MessageMinAmount,
+    /// Seconds after which an unclaimed tip may be reclaimed by its sender
+    ClaimWindow,
+    /// Flag set while the platform is paused
+    Paused,
+    /// Message attached to tips sent without one
+    DefaultMessage,
+    /// Admin-managed on/off switches for newer entrypoints, by feature name
+    FeatureFlags,
+    /// Flag set when senders may tip themselves
+    AllowSelfTip,
+    /// Storage layout version the contract's state was written with
+    Version,
+    /// Sender rate limit as (max tips, window in seconds)
+    RateLimitConfig,
+    /// Share of each withdrawal kept as a platform fee, in basis points
+    WithdrawFeeBps,
+    /// Seconds after sending during which a tip's message may be edited
+    EditGracePeriod,
+    /// Minimum seconds between two withdrawals by the same user
+    WithdrawCooldown,
+    /// Flag set when tips may only go to registered recipients
+    RequireRegistration,
+    /// Flag set when new tips get hash-derived ids instead of counter ids
+    HashedTipIds,
+    /// Marks a token whose tips are paused
+    TokenPaused(Address),
+    /// Seconds after sending during which the latest tip may be unsent
+    UnsendWindow,
+    /// Token used by `send_tip_default`
+    DefaultToken,
+    /// Most recipients accepted by one batch or split tip
+    MaxBatch,
+    /// Seconds without activity after which a balance may be swept
+    Dormancy,
+    /// Address allowed, besides the admin, to settle conditional tips
+    Oracle,
+    /// Sequence number of the most recently published event
+    EventSeq,
+    /// Number of legacy tips migrated so far while a migration is running
+    MigrationCursor,
+}
+
+/// Keys for admin approvals, the audit log and per-user admin flags
+#[contracttype]
+#[derive(Clone)]
+pub enum AdminKey {
+    /// Marks a user whose withdrawals are on hold
+    Frozen(Address),
+    /// Number of admin audit log entries
+    AuditCount,
+    /// A single admin audit log entry, keyed by its sequence number
+    AuditEntry(u64),
+    /// Admin set whose approvals are counted for sensitive actions
+    Admins,
+    /// Number of distinct admin approvals a sensitive action needs
+    AdminThreshold,
+    /// Number of admin proposals ever made; also the id of the most recent one
+    ProposalCount,
+    /// A single admin proposal, keyed by its id
+    Proposal(u64),
+    /// Marks an address that pays no platform fee
+    FeeExempt(Address),
+    /// Marks a user onboarded by the admin
+    Registered(Address),
+}
+
+/// Keys for tips, their indexes and the escrows tips are paid from
+#[contracttype]
+#[derive(Clone)]
+pub enum TipKey {
+    /// Number of tips ever sent; also the id of the most recent tip
+    TipCount,
+    /// A single tip, keyed by its id
+    Tip(u64),
+    /// Ids of the tips sent by a user, oldest first
+    SenderTips(Address),
+    /// Ids of the tips received by a user, oldest first
+    RecipientTips(Address),
+    /// Recipient's acknowledgement reaction to a tip
+    Ack(u64),
+    /// Marks a tip that has been fully refunded or reclaimed by its sender
+    Refunded(u64),
+    /// Active match pledge for a recipient in a token: (recipient, token)
+    Match(Address, Address),
+    /// Sponsor and amount of the match a tip received and still holds
+    Matched(u64),
+    /// Sum of all unspent pledges held in escrow for a token
+    TotalEscrow(Address),
+    /// Number of pools ever created; also the id of the most recent one
+    PoolCount,
+    /// A shared tip pool, keyed by its id
+    Pool(u64),
+    /// Amount a member has claimed from a pool: (pool id, member)
+    PoolClaimed(u64, Address),
+    /// Tip id created under a client-supplied idempotency key
+    Idem(BytesN<32>),
+    /// Id of the tip with a given sequence number, when it has a hashed id
+    TipAt(u64),
+    /// Sequence number of a tip with a hashed id
+    TipSeq(u64),
+    /// Cumulative amount of a tip returned through partial refunds
+    RefundedAmount(u64),
+    /// Id of the most recent tip sent by a user
+    LastTip(Address),
+    /// Number of conditional tips ever created; also the latest id
+    ConditionalCount,
+    /// A conditional tip awaiting release or cancellation, keyed by its id
+    Conditional(u64),
+}
+
+/// Keys for users' balances, profiles and withdrawals, and the fee and liability books
+#[contracttype]
+#[derive(Clone)]
+pub enum AccountKey {
+    /// Balance of a user for a specific token: (user, token)
+    Balance(Address, Address),
+    /// Aggregated activity statistics for a user
+    Profile(Address),
+    /// Platform fees accrued in a token and not yet withdrawn
+    FeesCollected(Address),
+    /// Sum of all users' available balances in a token
+    TotalLiability(Address),
+    /// Tokens a user holds a balance in, in order of first receipt
+    UserTokens(Address),
+    /// Highest tip id a user has claimed in a token: (user, token)
+    ClaimedThrough(Address, Address),
+    /// Number of withdrawals ever made; also the id of the most recent one
+    WithdrawalCount,
+    /// A single withdrawal record, keyed by its id
+    Withdrawal(u64),
+    /// Ids of the withdrawals made from a user's balance, oldest first
+    UserWithdrawals(Address),
+    /// Handle registered by a user
+    Handle(Address),
+    /// User that owns a handle
+    HandleOwner(String),
+    /// Balance threshold alert for a user and token: (user, token)
+    Alert(Address, Address),
+    /// A sender's current rate-limit window as (window start, tips sent)
+    RateLimit(Address),
+    /// Personal tip goal for a user and token: (user, token)
+    Goal(Address, Address),
+    /// All platform fees ever accrued in a token, including withdrawn ones
+    FeesLifetime(Address),
+    /// Timestamp of a user's most recent withdrawal
+    LastWithdraw(Address),
+    /// Timestamp a balance was last credited or debited: (user, token)
+    LastActivity(Address, Address),
+    /// Address a user has authorized to withdraw on their behalf
+    Delegate(Address),
+    /// Recipient's limit on tips accepted per window: (cap, window seconds)
+    ReceiveCap(Address, Address),
+    /// Recipient's current receive window: (window start, amount received)
+    ReceivedInWindow(Address, Address),
+    /// Dormant balances swept into a community pool, per token
+    CharityPool(Address),
+}
+
+/// Keys for platform statistics and rankings derived from tips
+#[contracttype]
+#[derive(Clone)]
+pub enum StatsKey {
+    /// Number of tips a supporter has sent a recipient: (recipient, supporter)
+    SupporterTips(Address, Address),
+    /// A recipient's (one-time, repeat) supporter counts
+    Retention(Address),
+    /// Number of distinct addresses that have sent a tip
+    UniqueSenders,
+    /// Number of distinct addresses that have received a tip
+    UniqueRecipients,
+    /// Set while a user is counted as a unique sender
+    CountedSender(Address),
+    /// Set while a user is counted as a unique recipient
+    CountedRecipient(Address),
+    /// Tipped tokens with their total volume, highest volume first
+    TokenVolumeList,
+    /// Total tipped from one address to another, across tokens: (from, to)
+    PairVolume(Address, Address),
+    /// Highest-volume (from, to, volume) pairs, highest first
+    TopPairs,
+    /// Top recipients of a token by total received, highest first
+    Leaderboard(Address),
+    /// Total a user received in public tips per token, ranked by the leaderboard
+    PublicReceived(Address, Address),
 }
 
 // ============================================================================
@@ -89,11 +614,26 @@ impl MicrotipContract {
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
-    pub fn init(env: Env) {
-        // Initialize contract state if needed
-        // This is where you would set up initial configuration
-        let contract_initialized = Symbol::new(&env, "init");
-        env.storage().instance().set(&contract_initialized, &true);
+    /// * `admin` - Address allowed to manage platform configuration
+    /// 
+    /// # Panics
+    /// - If the contract has already been initialized
+    pub fn init(env: Env, admin: Address) {
+        // Guard against re-initialization overwriting the admin
+        assert!(
+            !env.storage().instance().has(&ConfigKey::Initialized),
+            "Contract already initialized"
+        );
+
+        env.storage().instance().set(&ConfigKey::Initialized, &true);
+        env.storage().instance().set(&ConfigKey::Admin, &admin);
+        env.storage().instance().set(&ConfigKey::Version, &VERSION);
+    }
+
+    /// Returns the version of the deployed contract code
+    /// Off-chain clients can use it to check compatibility after upgrades
+    pub fn version(_env: Env) -> u32 {
+        VERSION
     }
 
     /// Sends a tip from one address to another
@@ -105,7 +645,7 @@ impl MicrotipContract {
     /// * `to` - Address of the tip recipient
     /// * `token` - Contract address of the token to send
     /// * `amount` - Amount of the tip (in smallest token units)
-    /// * `message` - Optional message to attach to the tip (max 256 chars)
+    /// * `message` - Optional message to attach to the tip (max 256 chars by default)
     /// 
     /// # Returns
     /// Returns the unique ID of the created tip
@@ -125,362 +665,8708 @@ impl MicrotipContract {
         // Verify the sender (authorization check)
         from.require_auth();
 
-        // Validation: Ensure amount is positive
-        assert!(amount > 0, "Tip amount must be greater than zero");
-
-        // Validation: Prevent self-tipping
-        assert!(from != to, "Cannot send a tip to yourself");
-
-        // Validation: Ensure message is not excessively long
-        assert!(message.len() <= 256, "Message must be 256 characters or less");
-
-        // Get the token contract client to handle transfers
-        let token_client = TokenClient::new(&env, &token);
-
-        // Transfer the tip amount from sender to contract
-        token_client.transfer(&from, &env.current_contract_address(), &amount);
-
-        // Get the current timestamp for recording when the tip was sent
-        let timestamp = env.ledger().timestamp();
-
-        // Generate a unique tip ID (could be based on ledger sequence + counter)
-        let tip_id = env.ledger().sequence();
-
-        // Create a Tip structure containing all the metadata
-        let tip = Tip {
-            from: from.clone(),
-            to: to.clone(),
-            amount,
-            message: message.clone(),
-            timestamp,
-            token: token.clone(),
-        };
-
-        // Store the tip in contract storage using a unique key
-        let tip_key = Symbol::new(&env, "tip");
-        let mut tips: Vec<Tip> = env
-            .storage()
-            .instance()
-            .get(&tip_key)
-            .unwrap_or_else(|| Vec::new(&env));
-        tips.push_back(tip);
-        env.storage().instance().set(&tip_key, &tips);
-
-        // Update the recipient's balance
-        Self::update_balance(&env, &to, &token, amount, true);
-
-        // Update user profiles for statistics tracking
-        Self::update_sender_profile(&env, &from, amount);
-        Self::update_recipient_profile(&env, &to, amount);
-
-        // Emit an event (if using Soroban event system)
-        // This allows off-chain listeners to track tips in real-time
-        env.events().publish((Symbol::new(&env, "tip_sent"),), (from, to, amount, timestamp));
-
-        // Return the tip ID for future reference
-        tip_id
+        let tip = Self::new_tip(&env, from, to, token, amount, message);
+        Self::process_tip(&env, tip)
     }
 
-    /// Allows a user to withdraw their accumulated tip balance
-    /// After withdrawal, the amount is transferred to the user's address
+    /// Sends a tip in the platform's default token
+    /// Behaves like `send_tip`, for deployments that only use one token
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
-    /// * `user` - Address of the user withdrawing funds
-    /// * `token` - Token contract address to withdraw
-    /// * `amount` - Amount to withdraw
+    /// * `from` - Address of the person sending the tip
+    /// * `to` - Address of the tip recipient
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `message` - Optional message to attach to the tip (max 256 chars by default)
+    /// 
+    /// # Returns
+    /// Returns the unique ID of the created tip
     /// 
     /// # Panics
-    /// - If user doesn't have sufficient available balance
-    pub fn withdraw(
-        env: Env,
-        user: Address,
-        token: Address,
-        amount: i128,
-    ) {
-        // Verify authorization - only the user can withdraw their own funds
-        user.require_auth();
-
-        // Validation: Ensure amount is positive
-        assert!(amount > 0, "Withdrawal amount must be greater than zero");
+    /// - If no default token is configured
+    /// - If the tip fails the usual tip validation
+    pub fn send_tip_default(env: Env, from: Address, to: Address, amount: i128, message: String) -> u64 {
+        from.require_auth();
 
-        // Retrieve the user's current balance for this token
-        let balance_key = Symbol::new(&env, &format!("balance_{}_{}", user, token));
-        let mut balance: Balance = env
+        let token: Address = env
             .storage()
             .instance()
-            .get(&balance_key)
-            .expect("User has no balance to withdraw");
-
-        // Validation: Ensure user has sufficient available balance
-        assert!(
-            balance.available >= amount,
-            "Insufficient available balance for withdrawal"
-        );
-
-        // Reduce the available balance
-        balance.available -= amount;
-        // Track total withdrawn
-        balance.withdrawn += amount;
-
-        // Update the balance in storage
-        env.storage().instance().set(&balance_key, &balance);
-
-        // Create token client to handle the actual transfer
-        let token_client = TokenClient::new(&env, &token);
-
-        // Transfer the withdrawn amount from contract to user
-        token_client.transfer(&env.current_contract_address(), &user, &amount);
-
-        // Emit withdrawal event for tracking
-        let timestamp = env.ledger().timestamp();
-        env.events().publish(
-            (Symbol::new(&env, "withdrawal"),),
-            (user, token, amount, timestamp),
-        );
+            .get(&ConfigKey::DefaultToken)
+            .expect("No default token configured");
+        let tip = Self::new_tip(&env, from, to, token, amount, message);
+        Self::process_tip(&env, tip)
     }
 
-    /// Retrieves the balance information for a user and specific token
-    /// Shows total received, available, and withdrawn amounts
+    /// Sends a tip and reports the recipient's updated balance
+    /// Behaves like `send_tip`; lets tip-jar widgets refresh without
+    /// another call
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
-    /// * `user` - Address of the user
-    /// * `token` - Token contract address
+    /// * `from` - Address of the person sending the tip
+    /// * `to` - Address of the tip recipient
+    /// * `token` - Contract address of the token to send
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `message` - Optional message to attach to the tip (max 256 chars by default)
     /// 
     /// # Returns
-    /// A Balance structure containing the user's balance information
-    pub fn get_balance(env: Env, user: Address, token: Address) -> Balance {
-        // Construct the storage key for this user's balance
-        let balance_key = Symbol::new(&env, &format!("balance_{}_{}", user, token));
+    /// A SendResult with the tip id and the recipient's new available balance
+    pub fn send_tip_with_balance(
+        env: Env,
+        from: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        message: String,
+    ) -> SendResult {
+        from.require_auth();
 
-        // Retrieve from storage, or return a default (zero) balance if not found
-        env.storage()
-            .instance()
-            .get(&balance_key)
-            .unwrap_or_else(|| Balance {
-                total_received: 0,
-                available: 0,
-                withdrawn: 0,
-                token: token.clone(),
-            })
+        let tip = Self::new_tip(&env, from, to.clone(), token.clone(), amount, message);
+        let tip_id = Self::process_tip(&env, tip);
+        SendResult {
+            tip_id,
+            recipient_available: Self::get_balance(env, to, token).available,
+        }
     }
 
-    /// Retrieves the user profile with aggregated statistics
-    /// Useful for displaying user activity on a dashboard
+    /// Sends a tip at most once per client-supplied idempotency key
+    /// Retrying with a key that was already used returns the original tip
+    /// id without moving funds again
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
-    /// * `user` - Address of the user
+    /// * `from` - Address of the person sending the tip
+    /// * `to` - Address of the tip recipient
+    /// * `token` - Contract address of the token to send
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `message` - Optional message to attach to the tip (max 256 chars by default)
+    /// * `idempotency_key` - Unique key chosen by the client for this tip
     /// 
     /// # Returns
-    /// A UserProfile structure containing activity statistics
-    pub fn get_user_profile(env: Env, user: Address) -> UserProfile {
-        // Construct the storage key for this user's profile
-        let profile_key = Symbol::new(&env, &format!("profile_{}", user));
+    /// The id of the tip created under this key
+    /// 
+    /// # Panics
+    /// - If the key was already used by a different sender
+    pub fn send_tip_idempotent(
+        env: Env,
+        from: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        message: String,
+        idempotency_key: BytesN<32>,
+    ) -> u64 {
+        from.require_auth();
 
-        // Retrieve from storage, or return a default profile if not found
-        env.storage()
-            .instance()
-            .get(&profile_key)
-            .unwrap_or_else(|| UserProfile {
-                tips_sent: 0,
-                tips_received: 0,
-                total_sent: 0,
-                total_received: 0,
-                first_interaction: env.ledger().timestamp(),
-            })
+        // A repeated key is a retry of the tip already sent
+        let key = TipKey::Idem(idempotency_key);
+        if let Some(tip_id) = env.storage().persistent().get::<_, u64>(&key) {
+            let original = Self::load_tip(&env, tip_id);
+            assert!(original.from == from, "Idempotency key already used");
+            return tip_id;
+        }
+
+        let tip = Self::new_tip(&env, from, to, token, amount, message);
+        let tip_id = Self::process_tip(&env, tip);
+        env.storage().persistent().set(&key, &tip_id);
+        tip_id
     }
 
-    /// Retrieves all tips sent to a specific user
-    /// Useful for displaying tip history on user dashboards
+    /// Sends a tip without publicly revealing the sender
+    /// The sender still authorizes the transfer and is recorded for refunds,
+    /// but public queries and events report a sentinel address instead
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
-    /// * `user` - Address to get tips for
+    /// * `from` - Address of the person sending the tip
+    /// * `to` - Address of the tip recipient
+    /// * `token` - Contract address of the token to send
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `message` - Optional message to attach to the tip (max 256 chars by default)
     /// 
     /// # Returns
-    /// A vector of Tip structures received by the user
-    pub fn get_tips_for_user(env: Env, user: Address) -> Vec<Tip> {
-        // Retrieve all tips from storage
-        let tip_key = Symbol::new(&env, "tip");
-        let all_tips: Vec<Tip> = env
-            .storage()
-            .instance()
-            .get(&tip_key)
-            .unwrap_or_else(|| Vec::new(&env));
+    /// Returns the unique ID of the created tip
+    pub fn send_tip_anonymous(
+        env: Env,
+        from: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        message: String,
+    ) -> u64 {
+        from.require_auth();
 
-        // Filter tips to only include those received by the specified user
-        let mut user_tips = Vec::new(&env);
-        for tip in all_tips.iter() {
-            if tip.to == user {
-                user_tips.push_back(tip);
-            }
-        }
+        let mut tip = Self::new_tip(&env, from, to, token, amount, message);
+        tip.anonymous = true;
+        Self::process_tip(&env, tip)
+    }
 
-        user_tips
+    /// Sends a tip that is kept out of public activity feeds
+    /// The recipient is credited exactly as for a regular tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address of the person sending the tip
+    /// * `to` - Address of the tip recipient
+    /// * `token` - Contract address of the token to send
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `message` - Optional message to attach to the tip (max 256 chars by default)
+    /// 
+    /// # Returns
+    /// Returns the unique ID of the created tip
+    pub fn send_tip_private(
+        env: Env,
+        from: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        message: String,
+    ) -> u64 {
+        from.require_auth();
+
+        let mut tip = Self::new_tip(&env, from, to, token, amount, message);
+        tip.public = false;
+        Self::process_tip(&env, tip)
     }
 
-    /// Retrieves the total number of tips in the system
-    /// Useful for displaying platform statistics
+    /// Sends a tip whose platform fee is paid by a sponsor
+    /// Supports gasless UX: the tipper authorizes and funds the tip itself,
+    /// while the sponsor authorizes and pays the fee
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
+    /// * `sponsor` - Address paying the platform fee
+    /// * `from` - Address of the person sending the tip
+    /// * `to` - Address of the tip recipient
+    /// * `token` - Contract address of the token to send
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `message` - Optional message to attach to the tip (max 256 chars by default)
     /// 
     /// # Returns
-    /// The count of all tips ever sent on the platform
-    pub fn get_total_tips_count(env: Env) -> u32 {
-        // Retrieve all tips from storage
-        let tip_key = Symbol::new(&env, "tip");
-        let tips: Vec<Tip> = env
-            .storage()
-            .instance()
-            .get(&tip_key)
-            .unwrap_or_else(|| Vec::new(&env));
+    /// Returns the unique ID of the created tip
+    pub fn send_tip_sponsored(
+        env: Env,
+        sponsor: Address,
+        from: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        message: String,
+    ) -> u64 {
+        // Both parties must sign: one for the tip, one for the fee
+        sponsor.require_auth();
+        from.require_auth();
 
-        // Return the length as the count
-        tips.len() as u32
+        let mut tip = Self::new_tip(&env, from, to, token, amount, message);
+        tip.sponsor = Some(sponsor);
+        Self::process_tip(&env, tip)
     }
 
-    // ========================================================================
-    // INTERNAL HELPER FUNCTIONS
-    // ========================================================================
+    /// Sends a tip tagged with a bookkeeping category
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address of the person sending the tip
+    /// * `to` - Address of the tip recipient
+    /// * `token` - Contract address of the token to send
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `message` - Optional message to attach to the tip (max 256 chars by default)
+    /// * `category` - Tag for the tip, e.g. `donation` or `bounty`
+    /// 
+    /// # Returns
+    /// Returns the unique ID of the created tip
+    pub fn send_tip_tagged(
+        env: Env,
+        from: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        message: String,
+        category: Symbol,
+    ) -> u64 {
+        from.require_auth();
 
-    /// Updates a user's balance when they receive a tip
-    /// Internal function called by send_tip
+        let mut tip = Self::new_tip(&env, from, to, token, amount, message);
+        tip.category = category;
+        Self::process_tip(&env, tip)
+    }
+
+    /// Thanks a tipper by sending them a tip back in the same token
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
-    /// * `user` - Address of the user receiving the tip
-    /// * `token` - Token contract address
-    /// * `amount` - Amount to add to the balance
-    /// * `is_deposit` - Whether this is a deposit (true) or withdrawal (false)
-    fn update_balance(env: &Env, user: &Address, token: &Address, amount: i128, is_deposit: bool) {
-        // Construct the storage key for this balance
-        let balance_key = Symbol::new(env, &format!("balance_{}_{}", user, token));
+    /// * `recipient` - Address that received the original tip
+    /// * `tip_id` - Id of the original tip
+    /// * `amount` - Amount to send back (in smallest token units)
+    /// * `message` - Optional message to attach to the tip
+    /// 
+    /// # Returns
+    /// Returns the unique ID of the new tip
+    /// 
+    /// # Panics
+    /// - If the original tip does not exist
+    /// - If the caller is not the original tip's recipient
+    /// - If the original tip was anonymous, since tipping back would reveal its sender
+    pub fn tip_back(env: Env, recipient: Address, tip_id: u64, amount: i128, message: String) -> u64 {
+        recipient.require_auth();
 
-        // Retrieve existing balance or create a new one
-        let mut balance: Balance = env
-            .storage()
-            .instance()
-            .get(&balance_key)
-            .unwrap_or_else(|| Balance {
-                total_received: 0,
-                available: 0,
-                withdrawn: 0,
-                token: token.clone(),
-            });
+        let original = Self::load_tip(&env, tip_id);
+        assert!(original.to == recipient, "Only the recipient can tip back");
+        assert!(!original.anonymous, "Cannot tip back an anonymous tip");
 
-        // Update balance values
-        if is_deposit {
-            balance.total_received += amount;
-            balance.available += amount;
-        }
+        let tip = Self::new_tip(&env, recipient, original.from, original.token, amount, message);
+        Self::process_tip(&env, tip)
+    }
 
-        // Save the updated balance to storage
-        env.storage().instance().set(&balance_key, &balance);
+    /// Lets a sender pull back a tip the recipient never claimed
+    /// A tip is claimed once the recipient withdraws in its token after
+    /// receiving it; until then, and once the claim window has passed, the
+    /// sender may reclaim it. Any sponsor match the tip received is reversed
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `sender` - Address of the original tip sender
+    /// * `tip_id` - Id of the tip to reclaim
+    /// 
+    /// # Panics
+    /// - If no claim window is configured or it has not yet passed
+    /// - If the caller is not the tip's sender
+    /// - If the tip was already claimed, refunded or reclaimed
+    /// - If the platform is paused
+    pub fn reclaim_expired_tip(env: Env, sender: Address, tip_id: u64) {
+        sender.require_auth();
+        Self::acquire_lock(&env);
+        Self::require_not_paused(&env);
+
+        let tip = Self::load_tip(&env, tip_id);
+        assert!(tip.from == sender, "Only the sender can reclaim a tip");
+
+        // Validation: The claim window must be configured and elapsed
+        let window: u64 = env.storage().instance().get(&ConfigKey::ClaimWindow).unwrap_or(0);
+        assert!(window > 0, "Tip reclaiming is disabled");
+        let expires_at = tip.timestamp.saturating_add(window);
+        assert!(env.ledger().timestamp() >= expires_at, "Claim window has not expired");
+
+        // Validation: Only tips still sitting unclaimed can be pulled back
+        assert!(!Self::is_claimed(&env, tip_id, &tip), "Tip already claimed");
+        let refunded_key = TipKey::Refunded(tip_id);
+        assert!(
+            !env.storage().persistent().has(&refunded_key),
+            "Tip already refunded"
+        );
+        env.storage().persistent().set(&refunded_key, &true);
+
+        // Remove what is left of the tip from the recipient's balance and return it
+        let amount = tip.amount - Self::partially_refunded(&env, tip_id);
+        Self::update_balance(&env, &tip.to, &tip.token, amount, false);
+        Self::reverse_match(&env, tip_id, &tip, amount, amount);
+        let token_client = TokenClient::new(&env, &tip.token);
+        token_client.transfer(&env.current_contract_address(), &sender, &amount);
+
+        // Anonymous tips keep their sender masked in the event
+        let shown_sender = Self::public_view(&env, tip).from;
+        let timestamp = env.ledger().timestamp();
+        env.events().publish(
+            (Symbol::new(&env, "tip_reclaimed"),),
+            (Self::next_event_seq(&env), tip_id, shown_sender, amount, timestamp),
+        );
+        Self::release_lock(&env);
     }
 
-    /// Updates the sender's user profile statistics
-    /// Internal function called by send_tip
+    /// Lets the sender fix a tip's message shortly after sending it
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
-    /// * `user` - Address of the user sending the tip
-    /// * `amount` - Amount of the tip sent
-    fn update_sender_profile(env: &Env, user: &Address, amount: i128) {
-        // Construct the storage key for this user's profile
-        let profile_key = Symbol::new(env, &format!("profile_{}", user));
+    /// * `from` - Address of the original tip sender
+    /// * `tip_id` - Id of the tip to edit
+    /// * `new_message` - Replacement message
+    /// 
+    /// # Panics
+    /// - If the caller is not the tip's sender
+    /// - If the edit grace period (300 seconds by default) has passed
+    /// - If the new message breaks the usual message limits
+    pub fn edit_tip_message(env: Env, from: Address, tip_id: u64, new_message: String) {
+        from.require_auth();
 
-        // Retrieve existing profile or create a new one
-        let mut profile: UserProfile = env
+        let mut tip = Self::load_tip(&env, tip_id);
+        assert!(tip.from == from, "Only the sender can edit a tip");
+
+        // Validation: Edits are only allowed within the grace period
+        let grace: u64 = env
             .storage()
             .instance()
-            .get(&profile_key)
-            .unwrap_or_else(|| UserProfile {
-                tips_sent: 0,
-                tips_received: 0,
-                total_sent: 0,
-                total_received: 0,
-                first_interaction: env.ledger().timestamp(),
-            });
+            .get(&ConfigKey::EditGracePeriod)
+            .unwrap_or(DEFAULT_EDIT_GRACE_SECS);
+        assert!(
+            env.ledger().timestamp() <= tip.timestamp.saturating_add(grace),
+            "Edit window has expired"
+        );
 
-        // Increment send statistics
-        profile.tips_sent += 1;
-        profile.total_sent += amount;
+        // Validation: The new message follows the same rules as when sending
+        assert!(
+            new_message.len() <= Self::max_message_len(&env),
+            "Message exceeds maximum length"
+        );
+        assert!(
+            new_message.is_empty() || tip.amount >= Self::message_min_amount(&env),
+            "Tip amount too small to include a message"
+        );
 
-        // Save the updated profile to storage
-        env.storage().instance().set(&profile_key, &profile);
+        tip.message = new_message;
+        env.storage().persistent().set(&TipKey::Tip(tip_id), &tip);
+
+        env.events().publish(
+            (Symbol::new(&env, "tip_edited"),),
+            (Self::next_event_seq(&env), tip_id),
+        );
     }
 
-    /// Updates the recipient's user profile statistics
-    /// Internal function called by send_tip
+    /// Reverses the sender's most recent tip shortly after sending it
+    /// The full amount goes back to the sender and the tip no longer counts
+    /// towards either party's balance or profile totals; the platform fee is
+    /// not returned, and any sponsor match the tip received is reversed
     /// 
     /// # Arguments
     /// * `env` - The Soroban environment
-    /// * `user` - Address of the user receiving the tip
-    /// * `amount` - Amount of the tip received
-    fn update_recipient_profile(env: &Env, user: &Address, amount: i128) {
-        // Construct the storage key for this user's profile
-        let profile_key = Symbol::new(env, &format!("profile_{}", user));
+    /// * `from` - Address of the tip sender
+    /// 
+    /// # Returns
+    /// The id of the unsent tip
+    /// 
+    /// # Panics
+    /// - If the sender has no tip left to unsend
+    /// - If the unsend window (60 seconds by default) has passed
+    /// - If the recipient has already withdrawn the tip or refunded any of it
+    pub fn unsend_last_tip(env: Env, from: Address) -> u64 {
+        from.require_auth();
+        Self::require_not_paused(&env);
 
-        // Retrieve existing profile or create a new one
-        let mut profile: UserProfile = env
+        // Each tip can be unsent at most once, so the marker is consumed here
+        let last_key = TipKey::LastTip(from.clone());
+        let tip_id: u64 = env
+            .storage()
+            .persistent()
+            .get(&last_key)
+            .expect("No tip to unsend");
+        env.storage().persistent().remove(&last_key);
+        let tip = Self::load_tip(&env, tip_id);
+
+        // Validation: Unsending is only allowed within the window
+        let window: u64 = env
             .storage()
             .instance()
-            .get(&profile_key)
-            .unwrap_or_else(|| UserProfile {
-                tips_sent: 0,
-                tips_received: 0,
-                total_sent: 0,
-                total_received: 0,
-                first_interaction: env.ledger().timestamp(),
-            });
+            .get(&ConfigKey::UnsendWindow)
+            .unwrap_or(DEFAULT_UNSEND_WINDOW_SECS);
+        assert!(
+            env.ledger().timestamp() <= tip.timestamp.saturating_add(window),
+            "Unsend window has expired"
+        );
 
-        // Increment receive statistics
-        profile.tips_received += 1;
-        profile.total_received += amount;
+        // Validation: The funds must still be sitting untouched in the recipient's balance
+        assert!(!Self::is_claimed(&env, tip_id, &tip), "Tip already claimed");
+        let refunded_key = TipKey::Refunded(tip_id);
+        assert!(
+            !env.storage().persistent().has(&refunded_key)
+                && Self::partially_refunded(&env, tip_id) == 0,
+            "Tip already refunded"
+        );
+        env.storage().persistent().set(&refunded_key, &true);
 
-        // Save the updated profile to storage
-        env.storage().instance().set(&profile_key, &profile);
+        // Roll the tip and its match back out of the recipient's balance, both profiles and the rankings
+        Self::update_balance(&env, &tip.to, &tip.token, tip.amount, false);
+        let matched = Self::reverse_match(&env, tip_id, &tip, tip.amount, tip.amount);
+        let balance_key = AccountKey::Balance(tip.to.clone(), tip.token.clone());
+        let mut balance: Balance = env.storage().instance().get(&balance_key).unwrap();
+        balance.total_received = balance
+            .total_received
+            .checked_sub(tip.amount + matched)
+            .expect("arithmetic overflow");
+        env.storage().instance().set(&balance_key, &balance);
+        Self::rollback_profiles(&env, &tip);
+
+        let token_client = TokenClient::new(&env, &tip.token);
+        token_client.transfer(&env.current_contract_address(), &from, &tip.amount);
+
+        let timestamp = env.ledger().timestamp();
+        env.events().publish(
+            (Symbol::new(&env, "tip_unsent"),),
+            (Self::next_event_seq(&env), tip_id, tip.amount, timestamp),
+        );
+        tip_id
     }
-}
 
-// ============================================================================
-// TESTS MODULE
-// ============================================================================
+    /// Publicly thanks the sender of a tip with a reaction
+    /// Acknowledging again replaces the previous reaction
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `recipient` - Address of the tip recipient
+    /// * `tip_id` - Id of the tip being acknowledged
+    /// * `reaction` - Short reaction code, e.g. `thanks`
+    /// 
+    /// # Panics
+    /// - If the caller is not the tip's recipient
+    pub fn acknowledge_tip(env: Env, recipient: Address, tip_id: u64, reaction: Symbol) {
+        recipient.require_auth();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use soroban_sdk::testutils::{Address as _, Ledger};
+        let tip = Self::load_tip(&env, tip_id);
+        assert!(tip.to == recipient, "Only the recipient can acknowledge a tip");
 
-    #[test]
-    fn test_send_tip() {
-        // Test the send_tip functionality
-        // This would include mocking the environment and token contracts
-        // For a complete implementation, you would test:
-        // 1. Successful tip sending
-        // 2. Balance updates
-        // 3. Profile updates
-        // 4. Event emissions
+        env.storage().persistent().set(&TipKey::Ack(tip_id), &reaction);
+
+        env.events().publish(
+            (Symbol::new(&env, "ack"),),
+            (Self::next_event_seq(&env), tip_id, recipient, reaction),
+        );
     }
 
-    #[test]
-    fn test_withdraw() {
-        // Test the withdraw functionality
-        // This would include:
-        // 1. Successful withdrawal
-        // 2. Insufficient balance scenarios
-        // 3. Proper balance deduction
+    /// Retrieves the recipient's reaction to a tip, if they have acknowledged it
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip_id` - Id of the tip
+    pub fn get_ack(env: Env, tip_id: u64) -> Option<Symbol> {
+        env.storage().persistent().get(&TipKey::Ack(tip_id))
     }
 
-    #[test]
-    fn test_validations() {
-        // Test input validations
-        // This would include:
-        // 1. Zero or negative amounts
-        // 2. Self-tipping prevention
-        // 3. Message length limits
+    /// Registers a short, human-readable handle for a user
+    /// Setting a new handle releases the user's previous one
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address registering the handle
+    /// * `handle` - Handle to register (1 to 32 bytes)
+    /// 
+    /// # Panics
+    /// - If the handle is empty or longer than 32 bytes
+    /// - If the handle is already owned by another user
+    pub fn set_handle(env: Env, user: Address, handle: String) {
+        user.require_auth();
+
+        // Validation: Handles must be short and non-empty
+        assert!(!handle.is_empty(), "Handle must not be empty");
+        assert!(handle.len() <= MAX_HANDLE_LEN, "Handle exceeds maximum length");
+
+        // Validation: Each handle belongs to at most one user
+        let owner_key = AccountKey::HandleOwner(handle.clone());
+        let owner: Option<Address> = env.storage().persistent().get(&owner_key);
+        if let Some(owner) = owner {
+            assert!(owner == user, "Handle already taken");
+            return;
+        }
+
+        // Free up the handle the user had before, if any
+        let handle_key = AccountKey::Handle(user.clone());
+        let previous: Option<String> = env.storage().persistent().get(&handle_key);
+        if let Some(previous) = previous {
+            env.storage().persistent().remove(&AccountKey::HandleOwner(previous));
+        }
+
+        env.storage().persistent().set(&handle_key, &handle);
+        env.storage().persistent().set(&owner_key, &user);
+    }
+
+    /// Looks up the user that owns a handle
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `handle` - Handle to resolve
+    /// 
+    /// # Panics
+    /// - If no user has registered the handle
+    pub fn resolve_handle(env: Env, handle: String) -> Address {
+        env.storage()
+            .persistent()
+            .get(&AccountKey::HandleOwner(handle))
+            .expect("Handle not found")
+    }
+
+    /// Retrieves the handle a user has registered, if any
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user
+    pub fn get_handle(env: Env, user: Address) -> Option<String> {
+        env.storage().persistent().get(&AccountKey::Handle(user))
+    }
+
+    /// Returns a tip to its original sender
+    /// Only the recipient may refund, and only from their available balance.
+    /// A configured reversal fee is kept by the platform. After partial
+    /// refunds, only the part not yet returned is refunded. Any sponsor match
+    /// the tip received is taken back from the recipient as well
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `recipient` - Address of the tip recipient
+    /// * `tip_id` - Id of the tip to refund
+    /// 
+    /// # Panics
+    /// - If the caller is not the tip's recipient
+    /// - If the tip has already been refunded
+    /// - If the recipient's available balance no longer covers the tip and its match
+    pub fn refund_tip(env: Env, recipient: Address, tip_id: u64) {
+        recipient.require_auth();
+
+        let tip = Self::load_tip(&env, tip_id);
+
+        // Validation: Only the recipient can give a tip back
+        assert!(tip.to == recipient, "Only the recipient can refund a tip");
+
+        // Validation: A tip can only be refunded once
+        let refunded_key = TipKey::Refunded(tip_id);
+        assert!(
+            !env.storage().persistent().has(&refunded_key),
+            "Tip already refunded"
+        );
+        env.storage().persistent().set(&refunded_key, &true);
+
+        let amount = tip.amount - Self::partially_refunded(&env, tip_id);
+        Self::reverse_match(&env, tip_id, &tip, amount, amount);
+        Self::return_to_sender(&env, tip_id, &tip, amount);
+    }
+
+    /// Returns part of a tip to its original sender
+    /// Successive partial refunds may add up to at most the tip amount; once
+    /// they reach it the tip counts as fully refunded. A matching share of
+    /// any sponsor match the tip received is taken back from the recipient
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `recipient` - Address of the tip recipient
+    /// * `tip_id` - Id of the tip to refund
+    /// * `amount` - Amount to return
+    /// 
+    /// # Panics
+    /// - If the caller is not the tip's recipient
+    /// - If `amount` is zero or negative, or more than the part not yet refunded
+    /// - If the recipient's available balance does not cover `amount`
+    pub fn refund_tip_partial(env: Env, recipient: Address, tip_id: u64, amount: i128) {
+        recipient.require_auth();
+
+        let tip = Self::load_tip(&env, tip_id);
+        assert!(tip.to == recipient, "Only the recipient can refund a tip");
+        assert!(
+            !env.storage().persistent().has(&TipKey::Refunded(tip_id)),
+            "Tip already refunded"
+        );
+
+        // Validation: Refunds can never add up to more than the tip
+        assert!(amount > 0, "Refund amount must be greater than zero");
+        let previously_refunded = Self::partially_refunded(&env, tip_id);
+        let refunded = previously_refunded
+            .checked_add(amount)
+            .expect("arithmetic overflow");
+        assert!(refunded <= tip.amount, "Refund exceeds tip amount");
+        Self::reverse_match(&env, tip_id, &tip, amount, tip.amount - previously_refunded);
+
+        env.storage()
+            .persistent()
+            .set(&TipKey::RefundedAmount(tip_id), &refunded);
+        if refunded == tip.amount {
+            env.storage().persistent().set(&TipKey::Refunded(tip_id), &true);
+        }
+
+        Self::return_to_sender(&env, tip_id, &tip, amount);
+    }
+
+    /// Splits a single tip between several recipients by relative weight
+    /// The full amount is transferred once and then divided; integer
+    /// rounding dust goes to the first recipient so nothing is lost
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address of the person sending the tip
+    /// * `token` - Contract address of the token to send
+    /// * `amount` - Total amount to split (in smallest token units)
+    /// * `recipients` - Addresses sharing the tip
+    /// * `shares` - Relative weight of each recipient, in the same order
+    /// * `message` - Optional message attached to every resulting tip
+    /// 
+    /// # Returns
+    /// The ids of the tips created, one per recipient with a non-zero share
+    /// 
+    /// # Panics
+    /// - If `recipients` and `shares` differ in length or are empty
+    /// - If there are more recipients than the batch limit (50 by default)
+    /// - If the shares sum to zero
+    /// - If any resulting tip fails the usual tip validation
+    pub fn send_split_tip(
+        env: Env,
+        from: Address,
+        token: Address,
+        amount: i128,
+        recipients: Vec<Address>,
+        shares: Vec<u32>,
+        message: String,
+    ) -> Vec<u64> {
+        from.require_auth();
+        Self::require_not_paused(&env);
+        Self::require_feature(&env, "split");
+
+        // Validation: Every recipient needs exactly one share
+        assert!(!recipients.is_empty(), "At least one recipient is required");
+        Self::require_batch_size(&env, recipients.len());
+        assert!(
+            recipients.len() == shares.len(),
+            "Recipients and shares must have the same length"
+        );
+        assert!(amount > 0, "Tip amount must be greater than zero");
+
+        let mut total_shares: i128 = 0;
+        for share in shares.iter() {
+            total_shares += share as i128;
+        }
+        assert!(total_shares > 0, "Shares must not sum to zero");
+
+        // Work out each recipient's cut, rounding down
+        let mut parts: Vec<i128> = Vec::new(&env);
+        let mut distributed: i128 = 0;
+        for share in shares.iter() {
+            let part = amount
+                .checked_mul(share as i128)
+                .expect("arithmetic overflow")
+                / total_shares;
+            parts.push_back(part);
+            distributed += part;
+        }
+
+        // Hand any rounding remainder to the first recipient
+        let first = parts.get_unchecked(0) + (amount - distributed);
+        parts.set(0, first);
+
+        // Build and validate every tip before moving any funds
+        let mut tips: Vec<Tip> = Vec::new(&env);
+        for i in 0..recipients.len() {
+            let part = parts.get_unchecked(i);
+            if part == 0 {
+                continue;
+            }
+            let tip = Self::new_tip(
+                &env,
+                from.clone(),
+                recipients.get_unchecked(i),
+                token.clone(),
+                part,
+                message.clone(),
+            );
+            Self::validate_tip(&env, &tip);
+            tips.push_back(tip);
+        }
+
+        Self::process_tips(&env, &from, &token, amount, tips)
+    }
+
+    /// Sends individual tips in one token to several recipients at once
+    /// The total is transferred once and a single platform fee is charged
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address of the person sending the tips
+    /// * `token` - Contract address of the token to send
+    /// * `recipients` - Addresses receiving a tip
+    /// * `amounts` - Amount for each recipient, in the same order
+    /// * `message` - Optional message attached to every tip
+    /// 
+    /// # Returns
+    /// The ids of the tips created, in recipient order
+    /// 
+    /// # Panics
+    /// - If batch tipping is disabled
+    /// - If `recipients` and `amounts` differ in length or are empty
+    /// - If there are more recipients than the batch limit (50 by default)
+    /// - If any tip fails the usual tip validation
+    pub fn send_tips_batch(
+        env: Env,
+        from: Address,
+        token: Address,
+        recipients: Vec<Address>,
+        amounts: Vec<i128>,
+        message: String,
+    ) -> Vec<u64> {
+        from.require_auth();
+        Self::require_not_paused(&env);
+        Self::require_feature(&env, "batch");
+
+        // Validation: Every recipient needs exactly one amount
+        assert!(!recipients.is_empty(), "At least one recipient is required");
+        Self::require_batch_size(&env, recipients.len());
+        assert!(
+            recipients.len() == amounts.len(),
+            "Recipients and amounts must have the same length"
+        );
+
+        // Build and validate every tip before moving any funds
+        let mut tips: Vec<Tip> = Vec::new(&env);
+        let mut total: i128 = 0;
+        for i in 0..recipients.len() {
+            let tip = Self::new_tip(
+                &env,
+                from.clone(),
+                recipients.get_unchecked(i),
+                token.clone(),
+                amounts.get_unchecked(i),
+                message.clone(),
+            );
+            Self::validate_tip(&env, &tip);
+            total = total.checked_add(tip.amount).expect("arithmetic overflow");
+            tips.push_back(tip);
+        }
+
+        Self::process_tips(&env, &from, &token, total, tips)
+    }
+
+    /// Like `send_tips_batch`, but skips invalid entries instead of aborting
+    /// Only the valid tips are funded; the platform fee is charged once if
+    /// any tip is sent
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address of the person sending the tips
+    /// * `token` - Contract address of the token to send
+    /// * `recipients` - Addresses receiving a tip
+    /// * `amounts` - Amount for each recipient, in the same order
+    /// * `message` - Optional message attached to every tip
+    /// 
+    /// # Returns
+    /// One entry per recipient: the new tip id, or None if the entry was invalid
+    /// 
+    /// # Panics
+    /// - If batch tipping is disabled
+    /// - If `recipients` and `amounts` differ in length or are empty
+    /// - If there are more recipients than the batch limit (50 by default)
+    pub fn send_tips_batch_lenient(
+        env: Env,
+        from: Address,
+        token: Address,
+        recipients: Vec<Address>,
+        amounts: Vec<i128>,
+        message: String,
+    ) -> Vec<Option<u64>> {
+        from.require_auth();
+        Self::require_not_paused(&env);
+        Self::require_feature(&env, "batch");
+
+        // Validation: Every recipient needs exactly one amount
+        assert!(!recipients.is_empty(), "At least one recipient is required");
+        Self::require_batch_size(&env, recipients.len());
+        assert!(
+            recipients.len() == amounts.len(),
+            "Recipients and amounts must have the same length"
+        );
+
+        // Keep only the entries that pass validation, remembering their slots
+        let mut tips: Vec<Tip> = Vec::new(&env);
+        let mut valid: Vec<bool> = Vec::new(&env);
+        let mut total: i128 = 0;
+        for i in 0..recipients.len() {
+            let tip = Self::new_tip(
+                &env,
+                from.clone(),
+                recipients.get_unchecked(i),
+                token.clone(),
+                amounts.get_unchecked(i),
+                message.clone(),
+            );
+            let ok = Self::check_tip(&env, &tip).is_ok();
+            if ok {
+                Self::record_received(&env, &tip);
+                total = total.checked_add(tip.amount).expect("arithmetic overflow");
+                tips.push_back(tip);
+            }
+            valid.push_back(ok);
+        }
+
+        let mut ids = Vec::new(&env);
+        if !tips.is_empty() {
+            ids = Self::process_tips(&env, &from, &token, total, tips);
+        }
+
+        // Line the created ids back up with the original entries
+        let mut results = Vec::new(&env);
+        let mut next = 0;
+        for ok in valid.iter() {
+            if ok {
+                results.push_back(Some(ids.get_unchecked(next)));
+                next += 1;
+            } else {
+                results.push_back(None);
+            }
+        }
+
+        results
+    }
+
+    /// Pledges to match tips sent to a recipient, up to a cap
+    /// The full cap is escrowed now; each later tip in `token` to the
+    /// recipient is matched one-for-one from it until it runs out
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `sponsor` - Address funding the match
+    /// * `recipient` - Address whose tips are matched
+    /// * `token` - Token the pledge is paid in
+    /// * `cap` - Most the sponsor will pay out in matches
+    /// 
+    /// # Panics
+    /// - If `cap` is zero or negative
+    /// - If the recipient already has an active match in this token
+    pub fn create_match(env: Env, sponsor: Address, recipient: Address, token: Address, cap: i128) {
+        sponsor.require_auth();
+        Self::acquire_lock(&env);
+        Self::require_not_paused(&env);
+
+        // Validation: The pledge must be positive and for an accepted token
+        assert!(cap > 0, "Match cap must be greater than zero");
+        assert!(Self::token_allowed(&env, &token), "Token is not allowed");
+
+        // Validation: One pledge per recipient and token at a time
+        let key = TipKey::Match(recipient.clone(), token.clone());
+        assert!(!env.storage().persistent().has(&key), "Match already active");
+
+        // Hold the whole cap so matches never depend on the sponsor later
+        let token_client = TokenClient::new(&env, &token);
+        token_client.transfer(&sponsor, &env.current_contract_address(), &cap);
+        Self::adjust_escrow(&env, &token, cap);
+
+        env.storage().persistent().set(
+            &key,
+            &MatchPledge {
+                sponsor: sponsor.clone(),
+                remaining: cap,
+            },
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "match_created"), recipient),
+            (Self::next_event_seq(&env), sponsor, token, cap),
+        );
+        Self::release_lock(&env);
+    }
+
+    /// Withdraws a sponsor's match pledge and returns what is left of it
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `sponsor` - Address that funded the match
+    /// * `recipient` - Address whose tips are matched
+    /// * `token` - Token the pledge is paid in
+    /// 
+    /// # Returns
+    /// The unused amount transferred back to the sponsor
+    /// 
+    /// # Panics
+    /// - If there is no active match for the recipient and token
+    /// - If the caller is not the pledge's sponsor
+    pub fn cancel_match(env: Env, sponsor: Address, recipient: Address, token: Address) -> i128 {
+        sponsor.require_auth();
+        Self::acquire_lock(&env);
+
+        let key = TipKey::Match(recipient.clone(), token.clone());
+        let pledge: MatchPledge = env.storage().persistent().get(&key).expect("No active match");
+        assert!(pledge.sponsor == sponsor, "Only the sponsor can cancel the match");
+
+        env.storage().persistent().remove(&key);
+        Self::adjust_escrow(&env, &token, -pledge.remaining);
+        let token_client = TokenClient::new(&env, &token);
+        token_client.transfer(&env.current_contract_address(), &sponsor, &pledge.remaining);
+
+        env.events().publish(
+            (Symbol::new(&env, "match_cancelled"), recipient),
+            (Self::next_event_seq(&env), sponsor, token, pledge.remaining),
+        );
+
+        Self::release_lock(&env);
+        pledge.remaining
+    }
+
+    /// Retrieves the active match pledge for a recipient and token, if any
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `recipient` - Address whose tips are matched
+    /// * `token` - Token the pledge is paid in
+    pub fn get_match(env: Env, recipient: Address, token: Address) -> Option<MatchPledge> {
+        env.storage().persistent().get(&TipKey::Match(recipient, token))
+    }
+
+    /// Sends a tip that is held in escrow until its condition is confirmed
+    /// The amount and platform fee are taken now; the admin or oracle later
+    /// releases the tip to the recipient or cancels it back to the sender
+    /// The tip counts against the recipient's receive cap when it is created
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address of the person sending the tip
+    /// * `to` - Address of the tip recipient
+    /// * `token` - Contract address of the token to send
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `condition_key` - Key identifying the condition the recipient must meet
+    /// 
+    /// # Returns
+    /// The id of the conditional tip (separate from tip ids)
+    /// 
+    /// # Panics
+    /// - If the tip fails the usual tip validation
+    pub fn create_conditional_tip(
+        env: Env,
+        from: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        condition_key: Symbol,
+    ) -> u64 {
+        from.require_auth();
+        Self::acquire_lock(&env);
+        Self::require_not_paused(&env);
+
+        let tip = Self::new_tip(&env, from.clone(), to.clone(), token.clone(), amount, String::from_str(&env, ""));
+        Self::validate_tip(&env, &tip);
+        Self::enforce_rate_limit(&env, &from, 1);
+
+        // Hold the funds so the release never depends on the sender later
+        let token_client = TokenClient::new(&env, &token);
+        token_client.transfer(&from, &env.current_contract_address(), &amount);
+        Self::charge_fee(&env, &from);
+        Self::adjust_escrow(&env, &token, amount);
+
+        let count: u64 = env.storage().instance().get(&TipKey::ConditionalCount).unwrap_or(0);
+        let id = count.checked_add(1).expect("arithmetic overflow");
+        env.storage().instance().set(&TipKey::ConditionalCount, &id);
+        env.storage().persistent().set(
+            &TipKey::Conditional(id),
+            &ConditionalTip {
+                tip,
+                condition: condition_key.clone(),
+            },
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "cond_tip_created"), to),
+            (Self::next_event_seq(&env), id, condition_key, amount),
+        );
+
+        Self::release_lock(&env);
+        id
+    }
+
+    /// Credits a conditional tip to its recipient once its condition is met
+    /// The receive cap was checked at creation, so release is never blocked by it
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `authorizer` - The admin or the configured oracle
+    /// * `conditional_id` - Id of the conditional tip
+    /// 
+    /// # Returns
+    /// The id of the tip credited to the recipient
+    /// 
+    /// # Panics
+    /// - If the caller is neither the admin nor the oracle
+    /// - If the conditional tip does not exist or was already settled
+    pub fn release_conditional_tip(env: Env, authorizer: Address, conditional_id: u64) -> u64 {
+        Self::require_condition_authorizer(&env, &authorizer);
+        Self::acquire_lock(&env);
+        Self::require_not_paused(&env);
+
+        let mut tip = Self::take_conditional_tip(&env, conditional_id).tip;
+        Self::adjust_escrow(&env, &tip.token, -tip.amount);
+
+        // The tip enters the feeds when it is released, not when it was escrowed
+        tip.timestamp = env.ledger().timestamp();
+        let tip_id = Self::credit_tip(&env, tip.clone());
+        Self::apply_match(&env, tip_id, &tip);
+
+        env.events().publish(
+            (Symbol::new(&env, "cond_tip_released"),),
+            (Self::next_event_seq(&env), conditional_id, tip_id),
+        );
+
+        Self::release_lock(&env);
+        tip_id
+    }
+
+    /// Returns a conditional tip to its sender when its condition failed
+    /// The platform fee is not returned; the amount no longer counts
+    /// against the recipient's receive cap
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `authorizer` - The admin or the configured oracle
+    /// * `conditional_id` - Id of the conditional tip
+    /// 
+    /// # Panics
+    /// - If the caller is neither the admin nor the oracle
+    /// - If the conditional tip does not exist or was already settled
+    pub fn cancel_conditional_tip(env: Env, authorizer: Address, conditional_id: u64) {
+        Self::require_condition_authorizer(&env, &authorizer);
+        Self::acquire_lock(&env);
+
+        let tip = Self::take_conditional_tip(&env, conditional_id).tip;
+        Self::adjust_escrow(&env, &tip.token, -tip.amount);
+        Self::unrecord_received(&env, &tip);
+        let token_client = TokenClient::new(&env, &tip.token);
+        token_client.transfer(&env.current_contract_address(), &tip.from, &tip.amount);
+
+        env.events().publish(
+            (Symbol::new(&env, "cond_tip_cancelled"),),
+            (Self::next_event_seq(&env), conditional_id, tip.amount),
+        );
+
+        Self::release_lock(&env);
+    }
+
+    /// Retrieves a conditional tip that has not been settled yet
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `conditional_id` - Id of the conditional tip
+    pub fn get_conditional_tip(env: Env, conditional_id: u64) -> Option<ConditionalTip> {
+        env.storage()
+            .persistent()
+            .get(&TipKey::Conditional(conditional_id))
+            .map(|mut conditional: ConditionalTip| {
+                conditional.tip = Self::public_view(&env, conditional.tip);
+                conditional
+            })
+    }
+
+    /// Creates a shared tip jar split evenly among its members
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `creator` - Address setting up the pool
+    /// * `members` - Addresses sharing the pool
+    /// * `token` - Token the pool accepts
+    /// 
+    /// # Returns
+    /// The id of the new pool (ids start at 1)
+    /// 
+    /// # Panics
+    /// - If `members` is empty or lists an address twice
+    pub fn create_pool(env: Env, creator: Address, members: Vec<Address>, token: Address) -> u64 {
+        creator.require_auth();
+
+        // Validation: A pool needs at least one member, each listed once
+        assert!(!members.is_empty(), "Pool must have members");
+        for i in 0..members.len() {
+            let member = members.get_unchecked(i);
+            assert!(
+                members.last_index_of(&member) == Some(i),
+                "Duplicate pool member"
+            );
+        }
+        assert!(Self::token_allowed(&env, &token), "Token is not allowed");
+
+        let count: u64 = env.storage().instance().get(&TipKey::PoolCount).unwrap_or(0);
+        let pool_id = count.checked_add(1).expect("arithmetic overflow");
+        env.storage().instance().set(&TipKey::PoolCount, &pool_id);
+        env.storage().persistent().set(
+            &TipKey::Pool(pool_id),
+            &Pool {
+                members,
+                token,
+                total_received: 0,
+            },
+        );
+
+        pool_id
+    }
+
+    /// Sends a tip to a shared pool rather than a single recipient
+    /// The platform fee is charged just as for a regular tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address of the person sending the tip
+    /// * `pool_id` - Id of the pool to tip
+    /// * `amount` - Amount of the tip (in smallest token units)
+    /// * `message` - Optional message published with the tip
+    /// 
+    /// # Panics
+    /// - If no pool exists with the given id
+    /// - If the tip fails the amount, message or token rules of a regular tip
+    pub fn tip_to_pool(env: Env, from: Address, pool_id: u64, amount: i128, message: String) {
+        from.require_auth();
+        Self::acquire_lock(&env);
+        Self::require_not_paused(&env);
+
+        // Validation: Same amount, message and token rules as a regular tip
+        let mut pool = Self::load_pool(&env, pool_id);
+        if let Err(reason) = Self::check_payment(&env, &pool.token, amount, &message) {
+            panic!("{}", reason);
+        }
+        Self::enforce_rate_limit(&env, &from, 1);
+
+        let token_client = TokenClient::new(&env, &pool.token);
+        token_client.transfer(&from, &env.current_contract_address(), &amount);
+        Self::charge_fee(&env, &from);
+
+        // Pool funds are held for the members until they claim
+        pool.total_received = pool
+            .total_received
+            .checked_add(amount)
+            .expect("arithmetic overflow");
+        Self::adjust_escrow(&env, &pool.token, amount);
+        env.storage().persistent().set(&TipKey::Pool(pool_id), &pool);
+
+        let timestamp = env.ledger().timestamp();
+        env.events().publish(
+            (Symbol::new(&env, "pool_tip"), pool_id),
+            (Self::next_event_seq(&env), from, amount, message, timestamp),
+        );
+        Self::release_lock(&env);
+    }
+
+    /// Pays a member their unclaimed share of a pool
+    /// Each member is entitled to an equal share of everything tipped to the
+    /// pool; when it does not divide evenly, the first members in the list
+    /// get one extra unit each, so shares always sum to the total
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `member` - Address of the pool member claiming
+    /// * `pool_id` - Id of the pool
+    /// 
+    /// # Returns
+    /// The amount paid out
+    /// 
+    /// # Panics
+    /// - If the caller is not a member of the pool
+    /// - If the member has nothing left to claim
+    pub fn claim_pool_share(env: Env, member: Address, pool_id: u64) -> i128 {
+        member.require_auth();
+        Self::acquire_lock(&env);
+        Self::require_not_paused(&env);
+
+        let pool = Self::load_pool(&env, pool_id);
+        let index = pool.members.first_index_of(&member).expect("Not a pool member");
+
+        // Work out the member's share of everything received so far
+        let count = pool.members.len() as i128;
+        let mut entitled = pool.total_received / count;
+        if (index as i128) < pool.total_received % count {
+            entitled += 1;
+        }
+
+        let claimed_key = TipKey::PoolClaimed(pool_id, member.clone());
+        let claimed: i128 = env.storage().persistent().get(&claimed_key).unwrap_or(0);
+        let amount = entitled - claimed;
+        assert!(amount > 0, "Nothing to claim");
+        env.storage().persistent().set(&claimed_key, &entitled);
+
+        Self::adjust_escrow(&env, &pool.token, -amount);
+        let token_client = TokenClient::new(&env, &pool.token);
+        token_client.transfer(&env.current_contract_address(), &member, &amount);
+
+        env.events().publish(
+            (Symbol::new(&env, "pool_claim"), pool_id),
+            (Self::next_event_seq(&env), member, amount),
+        );
+        Self::release_lock(&env);
+
+        amount
+    }
+
+    /// Retrieves a pool by its id
+    /// 
+    /// # Panics
+    /// - If no pool exists with the given id
+    pub fn get_pool(env: Env, pool_id: u64) -> Pool {
+        Self::load_pool(&env, pool_id)
+    }
+
+    /// Deposits a creator's own funds into their available balance
+    /// Useful for float or testing widgets; no tip is recorded, so the
+    /// deposit never shows up in feeds, indexes or profiles, and it does
+    /// not count as received towards goals or balance alerts
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address depositing into their own balance
+    /// * `token` - Token contract address to deposit
+    /// * `amount` - Amount to deposit (in smallest token units)
+    /// 
+    /// # Panics
+    /// - If amount is zero or negative
+    /// - If the token is not allowed, is paused or is not a token contract
+    pub fn fund_own_jar(env: Env, user: Address, token: Address, amount: i128) {
+        user.require_auth();
+        Self::acquire_lock(&env);
+        Self::require_not_paused(&env);
+
+        assert!(amount > 0, "Deposit amount must be greater than zero");
+        if let Err(reason) = Self::check_token(&env, &token) {
+            panic!("{}", reason);
+        }
+
+        let token_client = TokenClient::new(&env, &token);
+        token_client.transfer(&user, &env.current_contract_address(), &amount);
+        Self::add_available(&env, &user, &token, amount);
+
+        env.events().publish(
+            (Symbol::new(&env, "jar_funded"), user),
+            (Self::next_event_seq(&env), token, amount),
+        );
+        Self::release_lock(&env);
+    }
+
+    /// Allows a user to withdraw their accumulated tip balance
+    /// After withdrawal, the amount is transferred to the user's address
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user withdrawing funds
+    /// * `token` - Token contract address to withdraw
+    /// * `amount` - Amount to withdraw
+    /// 
+    /// # Panics
+    /// - If user doesn't have sufficient available balance
+    pub fn withdraw(
+        env: Env,
+        user: Address,
+        token: Address,
+        amount: i128,
+    ) {
+        // Verify authorization - only the user can withdraw their own funds
+        user.require_auth();
+        Self::enforce_withdraw_cooldown(&env, &user);
+
+        Self::process_withdrawal(&env, &user, &token, amount, &user);
+    }
+
+    /// Withdraws a user's tip balance to a different destination address
+    /// Behaves like `withdraw`, but the tokens are sent to `destination`
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user whose balance is withdrawn
+    /// * `token` - Token contract address to withdraw
+    /// * `amount` - Amount to withdraw
+    /// * `destination` - Address that receives the tokens
+    /// 
+    /// # Panics
+    /// - If user doesn't have sufficient available balance
+    /// - If the token restricts withdrawals and `destination` is not allowed
+    pub fn withdraw_to(
+        env: Env,
+        user: Address,
+        token: Address,
+        amount: i128,
+        destination: Address,
+    ) {
+        // Only the balance owner can direct where their funds go
+        user.require_auth();
+        Self::enforce_withdraw_cooldown(&env, &user);
+
+        Self::process_withdrawal(&env, &user, &token, amount, &destination);
+    }
+
+    /// Authorizes a manager or agent to withdraw on a user's behalf
+    /// Replaces any previous delegate
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address whose balance the delegate may withdraw
+    /// * `delegate` - Address allowed to trigger withdrawals
+    pub fn set_withdraw_delegate(env: Env, user: Address, delegate: Address) {
+        user.require_auth();
+        env.storage().persistent().set(&AccountKey::Delegate(user), &delegate);
+    }
+
+    /// Retrieves the withdrawal delegate a user has set, if any
+    pub fn get_withdraw_delegate(env: Env, user: Address) -> Option<Address> {
+        env.storage().persistent().get(&AccountKey::Delegate(user))
+    }
+
+    /// Withdraws a user's balance at the request of their delegate
+    /// The delegate authorizes the call, but the funds always go to the user
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `delegate` - Address of the user's registered delegate
+    /// * `user` - Address whose balance is withdrawn
+    /// * `token` - Token contract address to withdraw
+    /// * `amount` - Amount to withdraw
+    /// 
+    /// # Panics
+    /// - If the caller is not the user's registered delegate
+    /// - If user doesn't have sufficient available balance
+    pub fn withdraw_as_delegate(env: Env, delegate: Address, user: Address, token: Address, amount: i128) {
+        delegate.require_auth();
+
+        let registered: Option<Address> = env.storage().persistent().get(&AccountKey::Delegate(user.clone()));
+        assert!(registered == Some(delegate), "Caller is not the user's delegate");
+        Self::enforce_withdraw_cooldown(&env, &user);
+
+        Self::process_withdrawal(&env, &user, &token, amount, &user);
+    }
+
+    /// Withdraws balances in several tokens in a single call
+    /// The batch is atomic: if any withdrawal fails, none of them happen
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user withdrawing funds
+    /// * `tokens` - Token contract addresses to withdraw
+    /// * `amounts` - Amount to withdraw for each token, in the same order
+    /// 
+    /// # Panics
+    /// - If `tokens` and `amounts` differ in length
+    /// - If any single withdrawal would fail (e.g. insufficient balance)
+    pub fn withdraw_multi(env: Env, user: Address, tokens: Vec<Address>, amounts: Vec<i128>) {
+        user.require_auth();
+
+        // Validation: Every token needs exactly one amount
+        assert!(
+            tokens.len() == amounts.len(),
+            "Tokens and amounts must have the same length"
+        );
+
+        // The whole batch counts as a single withdrawal for the cooldown
+        Self::enforce_withdraw_cooldown(&env, &user);
+
+        // A panic part-way through reverts the earlier withdrawals too
+        for i in 0..tokens.len() {
+            Self::process_withdrawal(
+                &env,
+                &user,
+                &tokens.get_unchecked(i),
+                amounts.get_unchecked(i),
+                &user,
+            );
+        }
+    }
+
+    /// Asks for a `threshold_reached` event when a balance reaches a target
+    /// The alert fires once when the available balance rises to or above
+    /// the threshold; setting it again re-arms it
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address whose balance is watched
+    /// * `token` - Token contract address of the balance
+    /// * `threshold` - Available balance that triggers the alert
+    /// 
+    /// # Panics
+    /// - If `threshold` is zero or negative
+    pub fn set_balance_alert(env: Env, user: Address, token: Address, threshold: i128) {
+        user.require_auth();
+
+        assert!(threshold > 0, "Threshold must be greater than zero");
+        env.storage().persistent().set(
+            &AccountKey::Alert(user, token),
+            &BalanceAlert {
+                threshold,
+                triggered: false,
+            },
+        );
+    }
+
+    /// Retrieves the balance alert set for a user and token, if any
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address whose balance is watched
+    /// * `token` - Token contract address of the balance
+    pub fn get_balance_alert(env: Env, user: Address, token: Address) -> Option<BalanceAlert> {
+        env.storage().persistent().get(&AccountKey::Alert(user, token))
+    }
+
+    /// Sets a personal goal for the total a user receives in a token
+    /// Replacing a goal starts it over as not yet completed
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address setting the goal
+    /// * `token` - Token contract address the goal is counted in
+    /// * `target` - Total received amount that completes the goal
+    /// 
+    /// # Panics
+    /// - If `target` is zero or negative
+    pub fn set_goal(env: Env, user: Address, token: Address, target: i128) {
+        user.require_auth();
+
+        assert!(target > 0, "Goal target must be greater than zero");
+        env.storage().persistent().set(
+            &AccountKey::Goal(user, token),
+            &Goal {
+                target,
+                completed: false,
+            },
+        );
+    }
+
+    /// Reports progress toward a user's goal in a token
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user
+    /// * `token` - Token contract address the goal is counted in
+    /// 
+    /// # Returns
+    /// A tuple of (total received, target); the target is zero if no goal is set
+    pub fn get_goal_progress(env: Env, user: Address, token: Address) -> (i128, i128) {
+        let target = env
+            .storage()
+            .persistent()
+            .get::<_, Goal>(&AccountKey::Goal(user.clone(), token.clone()))
+            .map(|goal| goal.target)
+            .unwrap_or(0);
+        let received = Self::get_balance(env, user, token).total_received;
+        (received, target)
+    }
+
+    /// Limits how much a recipient accepts in a token per time window
+    /// Tips that would push the window's total over the cap are rejected
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `recipient` - Address setting the cap
+    /// * `token` - Token the cap applies to
+    /// * `cap` - Most accepted per window; zero removes the cap
+    /// * `window_secs` - Length of the window in seconds
+    /// 
+    /// # Panics
+    /// - If `cap` is negative
+    /// - If a cap is set with a zero-length window
+    pub fn set_receive_cap(env: Env, recipient: Address, token: Address, cap: i128, window_secs: u64) {
+        recipient.require_auth();
+
+        let key = AccountKey::ReceiveCap(recipient, token);
+        assert!(cap >= 0, "Cap cannot be negative");
+        if cap == 0 {
+            env.storage().persistent().remove(&key);
+            return;
+        }
+        assert!(window_secs > 0, "Window must be greater than zero");
+        env.storage().persistent().set(&key, &(cap, window_secs));
+    }
+
+    /// Retrieves a recipient's receive cap as (cap, window in seconds)
+    /// Returns None when the recipient has not set one
+    pub fn get_receive_cap(env: Env, recipient: Address, token: Address) -> Option<(i128, u64)> {
+        env.storage().persistent().get(&AccountKey::ReceiveCap(recipient, token))
+    }
+
+    /// Deletes a user's emptied balance record for a token
+    /// Lets users who are done with a token stop paying for its storage
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address closing their balance
+    /// * `token` - Token contract address of the balance
+    /// 
+    /// # Panics
+    /// - If the user has no balance record for the token
+    /// - If the available balance is not zero
+    pub fn close_account(env: Env, user: Address, token: Address) {
+        user.require_auth();
+
+        let balance_key = AccountKey::Balance(user.clone(), token.clone());
+        let balance: Balance = env
+            .storage()
+            .instance()
+            .get(&balance_key)
+            .expect("User has no balance to close");
+
+        // Validation: Funds must be withdrawn before the record can go
+        assert!(balance.available == 0, "Balance must be zero to close");
+
+        env.storage().instance().remove(&balance_key);
+        env.storage()
+            .persistent()
+            .remove(&AccountKey::LastActivity(user.clone(), token.clone()));
+        Self::remove_user_token(&env, &user, &token);
+    }
+
+    /// Retrieves the balance information for a user and specific token
+    /// Shows total received, available, and withdrawn amounts
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user
+    /// * `token` - Token contract address
+    /// 
+    /// # Returns
+    /// A Balance structure containing the user's balance information
+    pub fn get_balance(env: Env, user: Address, token: Address) -> Balance {
+        // Construct the storage key for this user's balance
+        let balance_key = AccountKey::Balance(user.clone(), token.clone());
+
+        // Retrieve from storage, or return a default (zero) balance if not found
+        env.storage()
+            .instance()
+            .get(&balance_key)
+            .unwrap_or_else(|| Balance {
+                total_received: 0,
+                available: 0,
+                withdrawn: 0,
+                token: token.clone(),
+            })
+    }
+
+    /// Retrieves a user's balance together with the token's decimals
+    /// Saves front-ends a separate call when formatting amounts
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user
+    /// * `token` - Token contract address
+    /// 
+    /// # Returns
+    /// A tuple of (balance, decimals); decimals default to 7 for tokens
+    /// that do not implement `decimals`
+    pub fn get_balance_with_decimals(env: Env, user: Address, token: Address) -> (Balance, u32) {
+        let token_client = TokenClient::new(&env, &token);
+        let decimals = match token_client.try_decimals() {
+            Ok(Ok(decimals)) => decimals,
+            _ => DEFAULT_TOKEN_DECIMALS,
+        };
+
+        (Self::get_balance(env, user, token), decimals)
+    }
+
+    /// Retrieves a user's balances across every token they have received
+    /// Saves clients from needing to know each token address up front
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user
+    /// 
+    /// # Returns
+    /// One Balance per token, in order of the first tip received in it
+    pub fn get_all_balances_for_user(env: Env, user: Address) -> Vec<Balance> {
+        let tokens: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&AccountKey::UserTokens(user.clone()))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut balances = Vec::new(&env);
+        for token in tokens.iter() {
+            balances.push_back(Self::get_balance(env.clone(), user.clone(), token));
+        }
+
+        balances
+    }
+
+    /// Retrieves a user's available balance in each of several tokens
+    /// Saves a round trip per token for "total available" widgets
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user
+    /// * `tokens` - Token contract addresses to look up
+    /// 
+    /// # Returns
+    /// (token, available) pairs in the order given, leaving out tokens with
+    /// nothing available
+    pub fn get_total_available(env: Env, user: Address, tokens: Vec<Address>) -> Vec<(Address, i128)> {
+        let mut available = Vec::new(&env);
+        for token in tokens.iter() {
+            let balance = Self::get_balance(env.clone(), user.clone(), token.clone());
+            if balance.available > 0 {
+                available.push_back((token, balance.available));
+            }
+        }
+
+        available
+    }
+
+    /// Retrieves a page of the platform-wide withdrawal ledger
+    /// Intended for auditors walking every payout in order
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `after_id` - Cursor from the previous page, or None to start at the beginning
+    /// * `limit` - Maximum number of withdrawals to return (capped at 50)
+    /// 
+    /// # Returns
+    /// A WithdrawalPage with the withdrawals and the cursor for the next page
+    pub fn get_withdrawals_paged(env: Env, after_id: Option<u64>, limit: u32) -> WithdrawalPage {
+        let limit = limit.min(MAX_PAGE_SIZE) as u64;
+        let count: u64 = env.storage().instance().get(&AccountKey::WithdrawalCount).unwrap_or(0);
+        // A cursor at the very last id has nothing after it
+        let Some(first) = after_id.unwrap_or(0).checked_add(1) else {
+            return WithdrawalPage {
+                withdrawals: Vec::new(&env),
+                next: None,
+            };
+        };
+        let last = count.min(first.saturating_add(limit).saturating_sub(1));
+
+        let mut withdrawals = Vec::new(&env);
+        let mut id = first;
+        while id <= last {
+            let withdrawal: Withdrawal = env
+                .storage()
+                .persistent()
+                .get(&AccountKey::Withdrawal(id))
+                .expect("Withdrawal not found");
+            withdrawals.push_back(withdrawal);
+            id += 1;
+        }
+
+        // Only hand out a cursor if there is something after this page
+        let next = if limit > 0 && last < count { Some(last) } else { None };
+
+        WithdrawalPage { withdrawals, next }
+    }
+
+    /// Retrieves a page of one user's withdrawal history
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address whose withdrawals to list
+    /// * `start` - Number of withdrawals to skip
+    /// * `limit` - Maximum number of withdrawals to return (capped at 50)
+    /// 
+    /// # Returns
+    /// The user's withdrawals, oldest first
+    pub fn get_withdrawals_for_user(env: Env, user: Address, start: u32, limit: u32) -> Vec<Withdrawal> {
+        let ids = Self::index_ids(&env, &AccountKey::UserWithdrawals(user));
+        let end = ids.len().min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+
+        let mut withdrawals = Vec::new(&env);
+        for i in start..end {
+            let withdrawal: Withdrawal = env
+                .storage()
+                .persistent()
+                .get(&AccountKey::Withdrawal(ids.get_unchecked(i)))
+                .expect("Withdrawal not found");
+            withdrawals.push_back(withdrawal);
+        }
+
+        withdrawals
+    }
+
+    /// Retrieves the user profile with aggregated statistics
+    /// Useful for displaying user activity on a dashboard
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user
+    /// 
+    /// # Returns
+    /// A UserProfile structure containing activity statistics
+    pub fn get_user_profile(env: Env, user: Address) -> UserProfile {
+        // Construct the storage key for this user's profile
+        let profile_key = AccountKey::Profile(user.clone());
+
+        // Retrieve from storage, or return a default profile if not found
+        env.storage()
+            .instance()
+            .get(&profile_key)
+            .unwrap_or_else(|| UserProfile {
+                tips_sent: 0,
+                tips_received: 0,
+                total_sent: 0,
+                total_received: 0,
+                first_interaction: env.ledger().timestamp(),
+                last_interaction: env.ledger().timestamp(),
+                streak: 0,
+                streak_updated: 0,
+            })
+    }
+
+    /// Retrieves all tips sent to a specific user
+    /// Useful for displaying tip history on user dashboards
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address to get tips for
+    /// 
+    /// # Returns
+    /// A vector of Tip structures received by the user
+    pub fn get_tips_for_user(env: Env, user: Address) -> Vec<Tip> {
+        // Look up the ids of every tip the user has received
+        let ids = Self::index_ids(&env, &TipKey::RecipientTips(user));
+
+        // Resolve each id to its stored tip
+        let mut user_tips = Vec::new(&env);
+        for id in ids.iter() {
+            user_tips.push_back(Self::public_view(&env, Self::load_tip(&env, id)));
+        }
+
+        user_tips
+    }
+
+    /// Counts the tips a user has received, without loading them
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the recipient
+    pub fn get_tip_count_for_user(env: Env, user: Address) -> u32 {
+        Self::index_ids(&env, &TipKey::RecipientTips(user)).len()
+    }
+
+    /// Counts the tips a user has sent, without loading them
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the sender
+    pub fn get_tip_count_for_sender(env: Env, user: Address) -> u32 {
+        Self::index_ids(&env, &TipKey::SenderTips(user)).len()
+    }
+
+    /// Retrieves a single tip by its id
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip_id` - Id returned by `send_tip`
+    /// 
+    /// # Returns
+    /// The stored Tip
+    /// 
+    /// # Panics
+    /// - If no tip exists with the given id
+    pub fn get_tip(env: Env, tip_id: u64) -> Tip {
+        Self::public_view(&env, Self::load_tip(&env, tip_id))
+    }
+
+    /// Looks up a tip by id without panicking when it does not exist
+    /// Named `find_tip` because the generated client already reserves
+    /// `try_get_tip` for the fallible form of `get_tip`
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip_id` - Id to probe
+    /// 
+    /// # Returns
+    /// The tip's public view, or None if no tip has that id
+    pub fn find_tip(env: Env, tip_id: u64) -> Option<Tip> {
+        env.storage()
+            .persistent()
+            .get(&TipKey::Tip(tip_id))
+            .map(|tip| Self::public_view(&env, tip))
+    }
+
+    /// Retrieves a tip along with its full lifecycle state
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip_id` - Id of the tip
+    /// 
+    /// # Returns
+    /// A TipStatus with the tip's refund and acknowledgement state
+    /// 
+    /// # Panics
+    /// - If no tip has that id
+    pub fn get_tip_full(env: Env, tip_id: u64) -> TipStatus {
+        let tip = Self::public_view(&env, Self::load_tip(&env, tip_id));
+        let refunded = env.storage().persistent().has(&TipKey::Refunded(tip_id));
+
+        // Full refunds return whatever partial refunds had not yet covered
+        let refunded_amount = if refunded {
+            tip.amount
+        } else {
+            Self::partially_refunded(&env, tip_id)
+        };
+
+        TipStatus {
+            tip,
+            refunded,
+            refunded_amount,
+            ack: env.storage().persistent().get(&TipKey::Ack(tip_id)),
+        }
+    }
+
+    /// Builds a receipt for a tip, identifying the contract that holds it
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip_id` - Id of the tip
+    /// 
+    /// # Returns
+    /// A TipReceipt built from the tip's public view
+    /// 
+    /// # Panics
+    /// - If no tip has that id
+    pub fn get_receipt(env: Env, tip_id: u64) -> TipReceipt {
+        let tip = Self::public_view(&env, Self::load_tip(&env, tip_id));
+        TipReceipt {
+            tip_id,
+            from: tip.from,
+            to: tip.to,
+            amount: tip.amount,
+            message: tip.message,
+            timestamp: tip.timestamp,
+            token: tip.token,
+            contract: env.current_contract_address(),
+            version: VERSION,
+            hash: Self::tip_hash(&env, tip_id),
+        }
+    }
+
+    /// Computes the deterministic receipt hash of a tip
+    /// The hash is the SHA-256 of the contract address, tip id and the tip's
+    /// public view in XDR form, so identical tips (or the same tip on another
+    /// deployment) get distinct receipts, and it never depends on (or reveals)
+    /// the sender of an anonymous tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip_id` - Id of the tip
+    /// 
+    /// # Panics
+    /// - If no tip exists with the given id
+    pub fn get_tip_hash(env: Env, tip_id: u64) -> BytesN<32> {
+        Self::tip_hash(&env, tip_id)
+    }
+
+    /// Checks a receipt hash held by a client against the tip's current state
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip_id` - Id of the tip
+    /// * `expected` - Receipt hash to check
+    /// 
+    /// # Returns
+    /// True if `expected` matches the hash of the stored tip
+    /// 
+    /// # Panics
+    /// - If no tip exists with the given id
+    pub fn verify_tip_hash(env: Env, tip_id: u64, expected: BytesN<32>) -> bool {
+        Self::tip_hash(&env, tip_id) == expected
+    }
+
+    /// Retrieves tips sent from one specific sender to one specific recipient
+    /// Useful for dispute resolution between two parties
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address of the sender
+    /// * `to` - Address of the recipient
+    /// * `start` - Number of matching tips to skip
+    /// * `limit` - Maximum number of tips to return (capped at 50)
+    /// 
+    /// # Returns
+    /// A page of tips from `from` to `to`, oldest first
+    pub fn get_tips_between(
+        env: Env,
+        from: Address,
+        to: Address,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Tip> {
+        // Walk the sender's index and keep only tips to the requested recipient;
+        // anonymous tips are left out so the sender index cannot unmask them
+        let ids = Self::index_ids(&env, &TipKey::SenderTips(from));
+        Self::page_tips(&env, &ids, start, limit, |tip| tip.to == to && !tip.anonymous)
+    }
+
+    /// Retrieves a recipient's tips carrying a given category
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the recipient
+    /// * `category` - Tag to filter by; `none` selects untagged tips
+    /// * `start` - Number of matching tips to skip
+    /// * `limit` - Maximum number of tips to return (capped at 50)
+    /// 
+    /// # Returns
+    /// A page of the recipient's tips in `category`, oldest first
+    pub fn get_tips_by_category(
+        env: Env,
+        user: Address,
+        category: Symbol,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Tip> {
+        let ids = Self::index_ids(&env, &TipKey::RecipientTips(user));
+        Self::page_tips(&env, &ids, start, limit, |tip| tip.category == category)
+    }
+
+    /// Retrieves a recipient's tips of at least a given amount
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the recipient
+    /// * `min_amount` - Smallest tip amount to include
+    /// * `start` - Number of matching tips to skip
+    /// * `limit` - Maximum number of tips to return (capped at 50)
+    /// 
+    /// # Returns
+    /// A page of the recipient's tips worth `min_amount` or more, oldest first
+    pub fn get_tips_for_user_min(
+        env: Env,
+        user: Address,
+        min_amount: i128,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Tip> {
+        let ids = Self::index_ids(&env, &TipKey::RecipientTips(user));
+        Self::page_tips(&env, &ids, start, limit, |tip| tip.amount >= min_amount)
+    }
+
+    /// Retrieves a recipient's tips sent within a time window
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the recipient
+    /// * `from_ts` - Earliest timestamp to include (inclusive)
+    /// * `to_ts` - Latest timestamp to include (inclusive)
+    /// * `start` - Number of matching tips to skip
+    /// * `limit` - Maximum number of tips to return (capped at 50)
+    /// 
+    /// # Returns
+    /// A page of the recipient's tips timestamped in the window, oldest first
+    /// 
+    /// # Panics
+    /// - If `from_ts` is after `to_ts`
+    pub fn get_tips_in_range(
+        env: Env,
+        user: Address,
+        from_ts: u64,
+        to_ts: u64,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Tip> {
+        assert!(from_ts <= to_ts, "Invalid time range");
+
+        let ids = Self::index_ids(&env, &TipKey::RecipientTips(user));
+        Self::page_tips(&env, &ids, start, limit, |tip| {
+            tip.timestamp >= from_ts && tip.timestamp <= to_ts
+        })
+    }
+
+    /// Retrieves the most recent tips across the whole platform
+    /// Walks backward from the newest tip, for homepage activity feeds
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `limit` - Maximum number of tips to return (capped at 50)
+    /// 
+    /// # Returns
+    /// Up to `limit` public tips in reverse-chronological order
+    pub fn get_recent_tips(env: Env, limit: u32) -> Vec<Tip> {
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let mut seq: u64 = env.storage().instance().get(&TipKey::TipCount).unwrap_or(0);
+        let mut tips = Vec::new(&env);
+
+        while seq > 0 && tips.len() < limit {
+            let tip_id = Self::tip_id_at(&env, seq);
+            let tip: Option<Tip> = env.storage().persistent().get(&TipKey::Tip(tip_id));
+            if let Some(tip) = tip {
+                // Private tips are credited as usual but kept out of the feed
+                if tip.public {
+                    tips.push_back(Self::public_view(&env, tip));
+                }
+            }
+            seq -= 1;
+        }
+
+        tips
+    }
+
+    /// Retrieves the platform's tips sent at or after a timestamp
+    /// Walks backward from the newest tip and stops at the first older one,
+    /// so indexers can poll incrementally
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `since_ts` - Earliest timestamp to include
+    /// * `limit` - Maximum number of tips to return (capped at 50)
+    /// 
+    /// # Returns
+    /// Up to `limit` public tips newer than `since_ts`, newest first
+    pub fn get_tips_since(env: Env, since_ts: u64, limit: u32) -> Vec<Tip> {
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let mut seq: u64 = env.storage().instance().get(&TipKey::TipCount).unwrap_or(0);
+        let mut tips = Vec::new(&env);
+
+        while seq > 0 && tips.len() < limit {
+            let tip_id = Self::tip_id_at(&env, seq);
+            let tip: Option<Tip> = env.storage().persistent().get(&TipKey::Tip(tip_id));
+            if let Some(tip) = tip {
+                // Sequence numbers increase with time, so everything further back is older too
+                if tip.timestamp < since_ts {
+                    break;
+                }
+                if tip.public {
+                    tips.push_back(Self::public_view(&env, tip));
+                }
+            }
+            seq -= 1;
+        }
+
+        tips
+    }
+
+    /// Retrieves tips a user received that are still waiting on an acknowledgement
+    /// Lets creators prioritise thanking their oldest supporters
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the recipient
+    /// * `older_than` - Minimum age in seconds for a tip to count as overdue
+    /// * `start` - Number of overdue tips to skip
+    /// * `limit` - Maximum number of tips to return (capped at 50)
+    /// 
+    /// # Returns
+    /// A page of unacknowledged tips older than `older_than`, oldest first
+    pub fn get_overdue_acknowledgements(
+        env: Env,
+        user: Address,
+        older_than: u64,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Tip> {
+        let now = env.ledger().timestamp();
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let ids = Self::index_ids(&env, &TipKey::RecipientTips(user));
+        let mut overdue = Vec::new(&env);
+        let mut skipped = 0;
+
+        for id in ids.iter() {
+            if overdue.len() >= limit {
+                break;
+            }
+            if env.storage().persistent().has(&TipKey::Ack(id)) {
+                continue;
+            }
+            let tip = Self::load_tip(&env, id);
+            if now.saturating_sub(tip.timestamp) <= older_than {
+                continue;
+            }
+            if skipped < start {
+                skipped += 1;
+                continue;
+            }
+            overdue.push_back(Self::public_view(&env, tip));
+        }
+
+        overdue
+    }
+
+    /// Retrieves how many of a recipient's supporters came back to tip again
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the recipient
+    /// 
+    /// # Returns
+    /// A tuple of (one_time, repeat) supporter counts
+    pub fn get_retention(env: Env, user: Address) -> (u32, u32) {
+        env.storage()
+            .persistent()
+            .get(&StatsKey::Retention(user))
+            .unwrap_or((0, 0))
+    }
+
+    /// Groups the tips a user has received by the supporter who sent them
+    /// Useful for creator feeds organised by supporter
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the recipient
+    /// * `start` - Number of received tips to skip
+    /// * `limit` - Maximum number of received tips to group (capped at 50)
+    /// 
+    /// # Returns
+    /// One entry per distinct sender in the page, in order of their first
+    /// tip, paired with the ids of the page's tips they sent to `user`
+    pub fn get_tips_grouped_by_sender(
+        env: Env,
+        user: Address,
+        start: u32,
+        limit: u32,
+    ) -> Vec<(Address, Vec<u64>)> {
+        let ids = Self::index_ids(&env, &TipKey::RecipientTips(user));
+        let start = start.min(ids.len());
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(ids.len());
+        let mut groups: Vec<(Address, Vec<u64>)> = Vec::new(&env);
+
+        for id in ids.slice(start..end).iter() {
+            let sender = Self::public_view(&env, Self::load_tip(&env, id)).from;
+
+            // Append to the sender's existing group, or start a new one
+            let mut found = false;
+            for i in 0..groups.len() {
+                let (addr, mut tip_ids) = groups.get_unchecked(i);
+                if addr == sender {
+                    tip_ids.push_back(id);
+                    groups.set(i, (addr, tip_ids));
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                let mut tip_ids = Vec::new(&env);
+                tip_ids.push_back(id);
+                groups.push_back((sender, tip_ids));
+            }
+        }
+
+        groups
+    }
+
+    /// Retrieves the total number of tips in the system
+    /// Useful for displaying platform statistics
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// 
+    /// # Returns
+    /// The count of all tips ever sent on the platform
+    pub fn get_total_tips_count(env: Env) -> u32 {
+        // The tip counter doubles as the total number of tips
+        let count: u64 = env.storage().instance().get(&TipKey::TipCount).unwrap_or(0);
+        count as u32
+    }
+
+    /// Retrieves aggregate platform metrics in a single call
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// 
+    /// # Returns
+    /// A PlatformStats structure with tip, user and token counts
+    pub fn get_platform_stats(env: Env) -> PlatformStats {
+        let storage = env.storage().instance();
+        PlatformStats {
+            total_tips: storage.get(&TipKey::TipCount).unwrap_or(0),
+            unique_senders: storage.get(&StatsKey::UniqueSenders).unwrap_or(0),
+            unique_recipients: storage.get(&StatsKey::UniqueRecipients).unwrap_or(0),
+            supported_tokens: storage.get(&ConfigKey::AllowedTokenCount).unwrap_or(0),
+        }
+    }
+
+    /// Retrieves the amount of a token actually held by the contract
+    /// Reads the token contract directly rather than internal accounting,
+    /// so operators can check the books against real holdings
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    /// 
+    /// # Returns
+    /// The contract's on-chain balance of `token`
+    pub fn get_contract_token_balance(env: Env, token: Address) -> i128 {
+        let token_client = TokenClient::new(&env, &token);
+        token_client.balance(&env.current_contract_address())
+    }
+
+    /// Retrieves the most-tipped tokens by total public volume
+    /// At most 50 tokens are tracked; tokens first tipped after that are not ranked
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `limit` - Maximum number of entries to return
+    /// 
+    /// # Returns
+    /// (token, volume) pairs sorted by volume, highest first
+    pub fn get_token_leaderboard(env: Env, limit: u32) -> Vec<(Address, i128)> {
+        let list: Vec<(Address, i128)> = env
+            .storage()
+            .instance()
+            .get(&StatsKey::TokenVolumeList)
+            .unwrap_or_else(|| Vec::new(&env));
+        list.slice(0..limit.min(list.len()))
+    }
+
+    /// Retrieves a token's top recipients by total received in public tips
+    /// Entries are refreshed whenever the recipient receives a public tip, and
+    /// at most 50 recipients are ranked
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    /// * `limit` - Maximum number of entries to return
+    /// 
+    /// # Returns
+    /// (recipient, total received publicly) pairs, highest first
+    pub fn get_leaderboard(env: Env, token: Address, limit: u32) -> Vec<(Address, i128)> {
+        let list = Self::leaderboard(&env, &token);
+        list.slice(0..limit.min(list.len()))
+    }
+
+    /// Retrieves a user's position on a token's leaderboard
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the recipient
+    /// * `token` - Token contract address
+    /// 
+    /// # Returns
+    /// The 1-based rank, or None if the user is not among the ranked recipients
+    pub fn get_rank(env: Env, user: Address, token: Address) -> Option<u32> {
+        Self::leaderboard(&env, &token)
+            .iter()
+            .position(|(entry, _)| entry == user)
+            .map(|index| index as u32 + 1)
+    }
+
+    /// Retrieves the sequence number of the most recently published event
+    /// Every event's data starts with its sequence number, so indexers can
+    /// compare against this to detect events they missed
+    pub fn get_event_seq(env: Env) -> u64 {
+        env.storage().instance().get(&ConfigKey::EventSeq).unwrap_or(0)
+    }
+
+    /// Retrieves the highest-volume sender to recipient pairs
+    /// Volumes add up amounts across tokens; anonymous and private tips are
+    /// not counted, and only the top 50 pairs are ranked
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `limit` - Maximum number of pairs to return
+    /// 
+    /// # Returns
+    /// (from, to, volume) entries sorted by volume, highest first
+    pub fn get_top_pairs(env: Env, limit: u32) -> Vec<(Address, Address, i128)> {
+        let list: Vec<(Address, Address, i128)> = env
+            .storage()
+            .instance()
+            .get(&StatsKey::TopPairs)
+            .unwrap_or_else(|| Vec::new(&env));
+        list.slice(0..limit.min(list.len()))
+    }
+
+    /// Returns the figures needed to reconcile a token's holdings
+    /// A discrepancy between the two values points at funds the internal
+    /// accounting does not explain
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    /// 
+    /// # Returns
+    /// A tuple of (contract_token_balance, total_fees_collected)
+    pub fn reconcile(env: Env, token: Address) -> (i128, i128) {
+        let held = Self::get_contract_token_balance(env.clone(), token.clone());
+
+        // Fees accrued but not yet withdrawn by the platform
+        let fees: i128 = env
+            .storage()
+            .instance()
+            .get(&AccountKey::FeesCollected(token))
+            .unwrap_or(0);
+
+        (held, fees)
+    }
+
+    /// Compares what the contract holds against what it owes for a token
+    /// Clients can display a solvency badge when `held >= owed`
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    /// 
+    /// # Returns
+    /// A tuple of (held, owed), where `owed` is the sum of users' available
+    /// balances plus accrued platform fees, escrowed funds and the charity pool
+    pub fn get_solvency(env: Env, token: Address) -> (i128, i128) {
+        let held = Self::get_contract_token_balance(env.clone(), token.clone());
+        let owed = Self::total_owed(&env, &token);
+        (held, owed)
+    }
+
+    // ========================================================================
+    // ADMIN FUNCTIONS
+    // ========================================================================
+
+    /// Restricts withdrawals of a token to a set of destination addresses
+    /// Applies platform-wide to both `withdraw` and `withdraw_to`
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token contract address to restrict
+    /// * `destinations` - Allowed destinations; an empty list lifts the restriction
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_token_withdraw_allowlist(
+        env: Env,
+        admin: Address,
+        token: Address,
+        destinations: Vec<Address>,
+        reason: Symbol,
+    ) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "withdraw_allowlist", &token, reason);
+
+        let key = ConfigKey::WithdrawAllowlist(token);
+        if destinations.is_empty() {
+            env.storage().instance().remove(&key);
+        } else {
+            env.storage().instance().set(&key, &destinations);
+        }
+    }
+
+    /// Adds a token to the tipping allowlist
+    /// Once any token is allowed, tips in unlisted tokens are rejected
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token contract address to approve
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn allow_token(env: Env, admin: Address, token: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "allow_token", &token, reason);
+
+        let key = ConfigKey::AllowedToken(token);
+        if env.storage().instance().has(&key) {
+            return;
+        }
+        env.storage().instance().set(&key, &true);
+
+        let count: u32 = env.storage().instance().get(&ConfigKey::AllowedTokenCount).unwrap_or(0);
+        env.storage().instance().set(&ConfigKey::AllowedTokenCount, &(count + 1));
+    }
+
+    /// Removes a token from the tipping allowlist
+    /// Removing the last token returns the platform to accepting all tokens
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token contract address to remove
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn disallow_token(env: Env, admin: Address, token: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "disallow_token", &token, reason);
+
+        let key = ConfigKey::AllowedToken(token);
+        if !env.storage().instance().has(&key) {
+            return;
+        }
+        env.storage().instance().remove(&key);
+
+        let count: u32 = env.storage().instance().get(&ConfigKey::AllowedTokenCount).unwrap_or(0);
+        env.storage().instance().set(&ConfigKey::AllowedTokenCount, &(count - 1));
+    }
+
+    /// Checks whether tips in a token are currently accepted
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    /// 
+    /// # Returns
+    /// True if the token is on the allowlist or the allowlist is empty
+    pub fn is_token_allowed(env: Env, token: Address) -> bool {
+        Self::token_allowed(&env, &token)
+    }
+
+    /// Configures the platform fee charged on every tip
+    /// The fee is pulled from the sender in `fee_token`, in addition to the tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `fee_token` - Token contract address the fee is paid in
+    /// * `fee_flat` - Flat fee per tip; zero disables the fee
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If `fee_flat` is negative
+    pub fn set_fee_config(
+        env: Env,
+        admin: Address,
+        fee_token: Address,
+        fee_flat: i128,
+        reason: Symbol,
+    ) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_fee_config", &fee_token, reason);
+
+        assert!(fee_flat >= 0, "Fee cannot be negative");
+
+        env.storage()
+            .instance()
+            .set(&ConfigKey::FeeConfig, &FeeConfig { fee_token, fee_flat });
+    }
+
+    /// Retrieves the current platform fee configuration
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// 
+    /// # Returns
+    /// The fee configuration, or None if no fee has been configured
+    pub fn get_fee_config(env: Env) -> Option<FeeConfig> {
+        env.storage().instance().get(&ConfigKey::FeeConfig)
+    }
+
+    /// Exempts an address from the platform fee, e.g. a verified partner
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `address` - Address whose exemption changes
+    /// * `exempt` - Whether the address should skip the fee
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_fee_exempt(
+        env: Env,
+        admin: Address,
+        address: Address,
+        exempt: bool,
+        reason: Symbol,
+    ) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_fee_exempt", &address, reason);
+
+        let key = AdminKey::FeeExempt(address);
+        if exempt {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+
+    /// Checks whether an address is exempt from the platform fee
+    pub fn is_fee_exempt(env: Env, address: Address) -> bool {
+        env.storage().persistent().has(&AdminKey::FeeExempt(address))
+    }
+
+    /// Retrieves fee accounting for a token
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    /// 
+    /// # Returns
+    /// A tuple of (collected over the contract's lifetime, currently
+    /// available to withdraw)
+    pub fn get_fee_stats(env: Env, token: Address) -> (i128, i128) {
+        let lifetime: i128 = env
+            .storage()
+            .instance()
+            .get(&AccountKey::FeesLifetime(token.clone()))
+            .unwrap_or(0);
+        let available: i128 = env
+            .storage()
+            .instance()
+            .get(&AccountKey::FeesCollected(token))
+            .unwrap_or(0);
+        (lifetime, available)
+    }
+
+    /// Previews how a tip would be split without sending it
+    /// The platform fee is a flat amount charged in the fee token on top of
+    /// the tip, so the recipient is always credited the full amount
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address the tip would be sent in
+    /// * `amount` - Tip amount (in smallest token units)
+    /// * `sender` - Address paying the fee, to account for exemptions; if
+    ///   None, the standard fee is reported
+    /// 
+    /// # Returns
+    /// A tuple of (amount credited to the recipient, fee charged in the
+    /// configured fee token)
+    /// 
+    /// # Panics
+    /// - If `amount` is zero or negative
+    /// - If the token is not allowed
+    pub fn preview_tip(env: Env, token: Address, amount: i128, sender: Option<Address>) -> (i128, i128) {
+        assert!(amount > 0, "Tip amount must be greater than zero");
+        assert!(Self::token_allowed(&env, &token), "Token is not allowed");
+
+        let fee = match sender {
+            Some(sender) => Self::fee_for(&env, &sender),
+            None => Self::standard_fee(&env),
+        };
+        (amount, fee.map(|(_, fee_flat)| fee_flat).unwrap_or(0))
+    }
+
+    /// Restricts tips to registered recipients, for closed platforms
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `required` - Whether recipients must be registered
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_require_registration(env: Env, admin: Address, required: bool, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_require_reg", &env.current_contract_address(), reason);
+
+        if required {
+            env.storage().instance().set(&ConfigKey::RequireRegistration, &true);
+        } else {
+            env.storage().instance().remove(&ConfigKey::RequireRegistration);
+        }
+    }
+
+    /// Switches new tips between counter ids and hash-derived ids
+    /// A hashed id comes from the tip's parties, timestamp and sequence
+    /// number, so integrators can compute it ahead of time. It falls back to
+    /// the counter on a collision, and for anonymous tips, whose id must not
+    /// reveal the sender. Existing tips keep their ids
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `enabled` - Whether new tips get hash-derived ids
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_hashed_tip_ids(env: Env, admin: Address, enabled: bool, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_hashed_tip_ids", &env.current_contract_address(), reason);
+
+        if enabled {
+            env.storage().instance().set(&ConfigKey::HashedTipIds, &true);
+        } else {
+            env.storage().instance().remove(&ConfigKey::HashedTipIds);
+        }
+    }
+
+    /// Onboards a user so they can receive tips while registration is required
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `user` - Address to register
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn register_user(env: Env, admin: Address, user: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "register_user", &user, reason);
+        env.storage().persistent().set(&AdminKey::Registered(user), &true);
+    }
+
+    /// Checks whether a user has been registered
+    pub fn is_registered(env: Env, user: Address) -> bool {
+        env.storage().persistent().has(&AdminKey::Registered(user))
+    }
+
+    /// Places a hold on a user's withdrawals, e.g. during a dispute
+    /// A frozen user can still receive tips
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `user` - Address of the user to freeze
+    /// * `reason` - Reason code recorded in the audit log, e.g. `dispute`
+    pub fn freeze_user(env: Env, admin: Address, user: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "freeze", &user, reason);
+        env.storage().persistent().set(&AdminKey::Frozen(user), &true);
+    }
+
+    /// Lifts a hold placed by `freeze_user`
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `user` - Address of the user to unfreeze
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn unfreeze_user(env: Env, admin: Address, user: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "unfreeze", &user, reason);
+        env.storage().persistent().remove(&AdminKey::Frozen(user));
+    }
+
+    /// Retrieves the most recent entries of the admin audit log
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `limit` - Maximum number of entries to return (capped at 50)
+    /// 
+    /// # Returns
+    /// Up to `limit` of the latest entries, oldest first
+    pub fn get_audit_log(env: Env, limit: u32) -> Vec<AuditEntry> {
+        let count: u64 = env.storage().instance().get(&AdminKey::AuditCount).unwrap_or(0);
+        let limit = limit.min(MAX_PAGE_SIZE) as u64;
+        let first = count.saturating_sub(limit) + 1;
+
+        let mut entries = Vec::new(&env);
+        for id in first..=count {
+            let entry: AuditEntry = env
+                .storage()
+                .persistent()
+                .get(&AdminKey::AuditEntry(id))
+                .expect("Audit entry not found");
+            entries.push_back(entry);
+        }
+
+        entries
+    }
+
+    /// Checks whether a user's withdrawals are currently on hold
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user
+    pub fn is_frozen(env: Env, user: Address) -> bool {
+        env.storage().persistent().has(&AdminKey::Frozen(user))
+    }
+
+    /// Sets the reversal fee kept from refunded tips
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `bps` - Fee in basis points of the refunded amount; zero disables it
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If `bps` exceeds 10000 (100%)
+    pub fn set_refund_fee_bps(env: Env, admin: Address, bps: u32, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_refund_fee", &env.current_contract_address(), reason);
+
+        assert!(bps as i128 <= BPS_DENOMINATOR, "Fee cannot exceed 10000 bps");
+        env.storage().instance().set(&ConfigKey::RefundFeeBps, &bps);
+    }
+
+    /// Sets the fee kept from each withdrawal
+    /// The user's balance is debited the full amount and receives the rest
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `bps` - Fee in basis points of the withdrawn amount; zero disables it
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If `bps` exceeds 1000 (10%)
+    pub fn set_withdrawal_fee_bps(env: Env, admin: Address, bps: u32, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_withdraw_fee", &env.current_contract_address(), reason);
+
+        assert!(bps <= MAX_WITHDRAW_FEE_BPS, "Fee cannot exceed 1000 bps");
+        env.storage().instance().set(&ConfigKey::WithdrawFeeBps, &bps);
+    }
+
+    /// Retrieves the fee kept from each withdrawal, in basis points
+    pub fn get_withdrawal_fee_bps(env: Env) -> u32 {
+        env.storage().instance().get(&ConfigKey::WithdrawFeeBps).unwrap_or(0)
+    }
+
+    /// Requires tips below an amount to be sent without a message
+    /// Discourages spam, since messages cost storage
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `amount` - Smallest tip that may carry a message; zero lifts the rule
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_message_min_amount(env: Env, admin: Address, amount: i128, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_msg_min_amount", &env.current_contract_address(), reason);
+
+        assert!(amount >= 0, "Amount cannot be negative");
+        env.storage().instance().set(&ConfigKey::MessageMinAmount, &amount);
+    }
+
+    /// Sets how long recipients have to claim a tip before the sender may reclaim it
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `secs` - Claim window in seconds; zero disables reclaiming
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_claim_window(env: Env, admin: Address, secs: u64, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_claim_window", &env.current_contract_address(), reason);
+        env.storage().instance().set(&ConfigKey::ClaimWindow, &secs);
+    }
+
+    /// Sets how long a user must be inactive before their balance may be swept
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `secs` - Dormancy period in seconds; zero disables sweeping
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_dormancy(env: Env, admin: Address, secs: u64, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_dormancy", &env.current_contract_address(), reason);
+        env.storage().instance().set(&ConfigKey::Dormancy, &secs);
+    }
+
+    /// Moves a dormant user's available balance into the token's charity pool
+    /// A balance is dormant once nothing has credited or debited it for the
+    /// configured dormancy period; balances with no recorded activity count
+    /// as dormant
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `user` - Address of the dormant user
+    /// * `token` - Token contract address to sweep
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Returns
+    /// The amount moved into the charity pool
+    /// 
+    /// # Panics
+    /// - If no dormancy period is configured
+    /// - If the balance has been active within the dormancy period
+    /// - If the user has no available balance in the token
+    pub fn sweep_dormant(
+        env: Env,
+        admin: Address,
+        user: Address,
+        token: Address,
+        reason: Symbol,
+    ) -> i128 {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "sweep_dormant", &user, reason);
+
+        // Validation: The user must have been inactive for the whole period
+        let dormancy: u64 = env.storage().instance().get(&ConfigKey::Dormancy).unwrap_or(0);
+        assert!(dormancy > 0, "Dormancy sweeping is disabled");
+        let last_active: u64 = env
+            .storage()
+            .persistent()
+            .get(&AccountKey::LastActivity(user.clone(), token.clone()))
+            .unwrap_or(0);
+        assert!(
+            env.ledger().timestamp() >= last_active.saturating_add(dormancy),
+            "Account is not dormant"
+        );
+
+        let amount = Self::get_balance(env.clone(), user.clone(), token.clone()).available;
+        assert!(amount > 0, "No balance to sweep");
+
+        // The funds stay in the contract, but now belong to the pool
+        Self::update_balance(&env, &user, &token, amount, false);
+        let pool_key = AccountKey::CharityPool(token.clone());
+        let pool: i128 = env.storage().instance().get(&pool_key).unwrap_or(0);
+        let pool = pool.checked_add(amount).expect("arithmetic overflow");
+        env.storage().instance().set(&pool_key, &pool);
+
+        env.events().publish(
+            (Symbol::new(&env, "dormant_swept"), token),
+            (Self::next_event_seq(&env), user, amount),
+        );
+
+        amount
+    }
+
+    /// Retrieves the dormant balances swept into a token's charity pool
+    pub fn get_charity_pool(env: Env, token: Address) -> i128 {
+        env.storage()
+            .instance()
+            .get(&AccountKey::CharityPool(token))
+            .unwrap_or(0)
+    }
+
+    /// Pays out a token's charity pool, e.g. to a community treasury
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token contract address
+    /// * `destination` - Address that receives the pool
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Returns
+    /// The amount paid out
+    pub fn withdraw_charity_pool(
+        env: Env,
+        admin: Address,
+        token: Address,
+        destination: Address,
+        reason: Symbol,
+    ) -> i128 {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "withdraw_charity", &token, reason);
+
+        let key = AccountKey::CharityPool(token.clone());
+        let amount: i128 = env.storage().instance().get(&key).unwrap_or(0);
+        if amount > 0 {
+            env.storage().instance().set(&key, &0i128);
+            let token_client = TokenClient::new(&env, &token);
+            token_client.transfer(&env.current_contract_address(), &destination, &amount);
+        }
+
+        env.events().publish(
+            (Symbol::new(&env, "charity_paid"), token),
+            (Self::next_event_seq(&env), destination, amount),
+        );
+
+        amount
+    }
+
+    /// Caps how many tips a single sender may send per time window
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `max_tips` - Tips allowed per window; zero disables the limit
+    /// * `window_secs` - Length of the window in seconds
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If a limit is set with a zero-length window
+    pub fn set_rate_limit(
+        env: Env,
+        admin: Address,
+        max_tips: u32,
+        window_secs: u64,
+        reason: Symbol,
+    ) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_rate_limit", &env.current_contract_address(), reason);
+
+        if max_tips == 0 {
+            env.storage().instance().remove(&ConfigKey::RateLimitConfig);
+            return;
+        }
+        assert!(window_secs > 0, "Window must be greater than zero");
+        env.storage()
+            .instance()
+            .set(&ConfigKey::RateLimitConfig, &(max_tips, window_secs));
+    }
+
+    /// Retrieves the sender rate limit as (max tips, window in seconds)
+    /// Returns None when no limit is configured
+    pub fn get_rate_limit(env: Env) -> Option<(u32, u64)> {
+        env.storage().instance().get(&ConfigKey::RateLimitConfig)
+    }
+
+    /// Sets how long senders may edit a tip's message after sending it
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `secs` - Grace period in seconds; zero disables editing after the sending ledger
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_edit_grace_period(env: Env, admin: Address, secs: u64, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_edit_grace", &env.current_contract_address(), reason);
+        env.storage().instance().set(&ConfigKey::EditGracePeriod, &secs);
+    }
+
+    /// Sets how long senders may unsend their most recent tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `secs` - Window in seconds; zero limits unsending to the sending ledger
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_unsend_window(env: Env, admin: Address, secs: u64, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_unsend_window", &env.current_contract_address(), reason);
+        env.storage().instance().set(&ConfigKey::UnsendWindow, &secs);
+    }
+
+    /// Sets the minimum time between two withdrawals by the same user
+    /// Limits how quickly a compromised key can drain a balance
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `secs` - Cooldown in seconds; zero disables it
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_withdraw_cooldown(env: Env, admin: Address, secs: u64, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_withdraw_cooldown", &env.current_contract_address(), reason);
+        env.storage().instance().set(&ConfigKey::WithdrawCooldown, &secs);
+    }
+
+    /// Retrieves the limits currently applied to tips and withdrawals
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// 
+    /// # Returns
+    /// A Limits structure reflecting the current configuration
+    pub fn get_limits(env: Env) -> Limits {
+        Limits {
+            // Amounts must be strictly positive
+            min_tip_amount: 1,
+            max_message_len: Self::max_message_len(&env),
+            message_min_amount: Self::message_min_amount(&env),
+            min_withdrawal_amount: 1,
+            max_batch: Self::max_batch(&env),
+        }
+    }
+
+    /// Retrieves the reversal fee kept from refunded tips, in basis points
+    pub fn get_refund_fee_bps(env: Env) -> u32 {
+        env.storage().instance().get(&ConfigKey::RefundFeeBps).unwrap_or(0)
+    }
+
+    /// Sets the most recipients accepted by one batch or split tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `max` - New batch size limit
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If `max` is zero or exceeds the ceiling of 200
+    pub fn set_max_batch(env: Env, admin: Address, max: u32, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_max_batch", &env.current_contract_address(), reason);
+
+        assert!(max > 0, "Batch limit must be greater than zero");
+        assert!(max <= MAX_BATCH_CEILING, "Batch limit exceeds ceiling");
+        env.storage().instance().set(&ConfigKey::MaxBatch, &max);
+    }
+
+    /// Sets the maximum length of tip messages
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `len` - New maximum message length in bytes
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If `len` exceeds the 1024-byte ceiling
+    pub fn set_max_message_len(env: Env, admin: Address, len: u32, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_max_msg_len", &env.current_contract_address(), reason);
+
+        assert!(len <= MAX_MESSAGE_LEN_CEILING, "Message length exceeds ceiling");
+        env.storage().instance().set(&ConfigKey::MaxMessageLen, &len);
+    }
+
+    /// Retrieves the maximum length of tip messages (256 unless configured)
+    pub fn get_max_message_len(env: Env) -> u32 {
+        Self::max_message_len(&env)
+    }
+
+    /// Sets the message attached to tips sent with an empty message
+    /// An empty `message` clears the default
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `message` - Default tip message
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If `message` exceeds the maximum message length
+    pub fn set_default_message(env: Env, admin: Address, message: String, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_default_msg", &env.current_contract_address(), reason);
+
+        if message.is_empty() {
+            env.storage().instance().remove(&ConfigKey::DefaultMessage);
+            return;
+        }
+        assert!(
+            message.len() <= Self::max_message_len(&env),
+            "Message exceeds maximum length"
+        );
+        env.storage().instance().set(&ConfigKey::DefaultMessage, &message);
+    }
+
+    /// Retrieves the default tip message (empty unless configured)
+    pub fn get_default_message(env: Env) -> String {
+        env.storage()
+            .instance()
+            .get(&ConfigKey::DefaultMessage)
+            .unwrap_or_else(|| String::from_str(&env, ""))
+    }
+
+    /// Sets the token used by `send_tip_default`
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token contract address to use by default
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_default_token(env: Env, admin: Address, token: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_default_token", &token, reason);
+        env.storage().instance().set(&ConfigKey::DefaultToken, &token);
+    }
+
+    /// Designates an oracle that may settle conditional tips alongside the admin
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `oracle` - Address confirming off-chain conditions
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_oracle(env: Env, admin: Address, oracle: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_oracle", &oracle, reason);
+        env.storage().instance().set(&ConfigKey::Oracle, &oracle);
+    }
+
+    /// Retrieves the token used by `send_tip_default`, if one is configured
+    pub fn get_default_token(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ConfigKey::DefaultToken)
+    }
+
+    /// Pauses the platform, halting tips and withdrawals
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn pause(env: Env, admin: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "pause", &env.current_contract_address(), reason);
+        env.storage().instance().set(&ConfigKey::Paused, &true);
+    }
+
+    /// Resumes the platform after a pause
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn unpause(env: Env, admin: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "unpause", &env.current_contract_address(), reason);
+        env.storage().instance().remove(&ConfigKey::Paused);
+    }
+
+    /// Checks whether the platform is currently paused
+    pub fn is_paused(env: Env) -> bool {
+        Self::paused(&env)
+    }
+
+    /// Pauses tipping in a single token while other tokens keep working
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token whose tips should be rejected
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn pause_token(env: Env, admin: Address, token: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "pause_token", &token, reason);
+        env.storage().instance().set(&ConfigKey::TokenPaused(token), &true);
+    }
+
+    /// Resumes tipping in a token paused with `pause_token`
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token to resume
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn unpause_token(env: Env, admin: Address, token: Address, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "unpause_token", &token, reason);
+        env.storage().instance().remove(&ConfigKey::TokenPaused(token));
+    }
+
+    /// Checks whether tipping in a token is paused
+    pub fn is_token_paused(env: Env, token: Address) -> bool {
+        env.storage().instance().has(&ConfigKey::TokenPaused(token))
+    }
+
+    /// Rescues a token's holding beyond everything the contract owes in an emergency
+    /// Only available while paused, so it cannot be used casually. Users'
+    /// available balances, escrowed funds, the charity pool and accrued
+    /// platform fees all stay in the contract; fees only leave through
+    /// `withdraw_fees` or an approved proposal
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token contract address to drain
+    /// * `destination` - Address that receives the rescued funds
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Returns
+    /// The amount transferred to `destination`
+    /// 
+    /// # Panics
+    /// - If the contract is not paused
+    pub fn emergency_withdraw(
+        env: Env,
+        admin: Address,
+        token: Address,
+        destination: Address,
+        reason: Symbol,
+    ) -> i128 {
+        Self::require_admin(&env, &admin);
+        assert!(Self::paused(&env), "Contract must be paused");
+        Self::record_audit(&env, &admin, "emergency_withdraw", &token, reason);
+
+        // Everything owed, fees included, is reserved and never drained
+        let reserved = Self::total_owed(&env, &token);
+
+        let token_client = TokenClient::new(&env, &token);
+        let held = token_client.balance(&env.current_contract_address());
+        let amount = (held - reserved).max(0);
+        if amount > 0 {
+            token_client.transfer(&env.current_contract_address(), &destination, &amount);
+        }
+
+        let timestamp = env.ledger().timestamp();
+        env.events().publish(
+            (Symbol::new(&env, "emergency_drain"), token),
+            (Self::next_event_seq(&env), admin, destination, amount, timestamp),
+        );
+
+        amount
+    }
+
+    /// Allows or forbids senders tipping themselves (forbidden by default)
+    /// Useful for seeding a personal tip jar or a charity pool
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `allowed` - Whether self-tips are accepted
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_allow_self_tip(env: Env, admin: Address, allowed: bool, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_allow_self_tip", &env.current_contract_address(), reason);
+
+        if allowed {
+            env.storage().instance().set(&ConfigKey::AllowSelfTip, &true);
+        } else {
+            env.storage().instance().remove(&ConfigKey::AllowSelfTip);
+        }
+    }
+
+    /// Checks whether senders may tip themselves
+    pub fn is_self_tip_allowed(env: Env) -> bool {
+        env.storage().instance().has(&ConfigKey::AllowSelfTip)
+    }
+
+    /// Turns a feature's entrypoints on or off
+    /// Features are enabled until explicitly disabled
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `feature` - Feature name, e.g. `batch` or `split`
+    /// * `enabled` - Whether the feature may be used
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn set_feature_flag(
+        env: Env,
+        admin: Address,
+        feature: Symbol,
+        enabled: bool,
+        reason: Symbol,
+    ) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "set_feature_flag", &env.current_contract_address(), reason);
+
+        let mut flags: Map<Symbol, bool> = env
+            .storage()
+            .instance()
+            .get(&ConfigKey::FeatureFlags)
+            .unwrap_or_else(|| Map::new(&env));
+        flags.set(feature, enabled);
+        env.storage().instance().set(&ConfigKey::FeatureFlags, &flags);
+    }
+
+    /// Checks whether a feature's entrypoints may be used
+    pub fn is_feature_enabled(env: Env, feature: Symbol) -> bool {
+        Self::feature_enabled(&env, &feature)
+    }
+
+    /// Replaces the contract code while keeping all stored state
+    /// The new code must already be uploaded to the network
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `new_wasm_hash` - Hash of the uploaded WASM to switch to
+    /// * `reason` - Reason code recorded in the audit log
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::require_single_admin(&env);
+        Self::record_audit(&env, &admin, "upgrade", &env.current_contract_address(), reason);
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    /// Pays out all platform fees accrued in a token
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `token` - Token whose fees to withdraw
+    /// * `destination` - Address that receives the fees
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Returns
+    /// The amount paid out
+    /// 
+    /// # Panics
+    /// - If a multi-admin set is configured (use `propose_action` instead)
+    pub fn withdraw_fees(
+        env: Env,
+        admin: Address,
+        token: Address,
+        destination: Address,
+        reason: Symbol,
+    ) -> i128 {
+        Self::require_admin(&env, &admin);
+        Self::require_single_admin(&env);
+        Self::record_audit(&env, &admin, "withdraw_fees", &token, reason);
+        Self::pay_out_fees(&env, &token, &destination)
+    }
+
+    /// Pays out the platform fees accrued in several tokens at once
+    /// Tokens with no fees accrued are skipped
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `tokens` - Tokens whose fees to withdraw
+    /// * `destination` - Address that receives the fees
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Returns
+    /// (token, amount) for each token that had fees to pay out
+    /// 
+    /// # Panics
+    /// - If a multi-admin set is configured (use `propose_action` instead)
+    pub fn withdraw_all_fees(
+        env: Env,
+        admin: Address,
+        tokens: Vec<Address>,
+        destination: Address,
+        reason: Symbol,
+    ) -> Vec<(Address, i128)> {
+        Self::require_admin(&env, &admin);
+        Self::require_single_admin(&env);
+
+        let mut paid = Vec::new(&env);
+        for token in tokens.iter() {
+            let fees: i128 = env
+                .storage()
+                .instance()
+                .get(&AccountKey::FeesCollected(token.clone()))
+                .unwrap_or(0);
+            if fees <= 0 {
+                continue;
+            }
+            Self::record_audit(&env, &admin, "withdraw_fees", &token, reason.clone());
+            let amount = Self::pay_out_fees(&env, &token, &destination);
+            paid.push_back((token, amount));
+        }
+        paid
+    }
+
+    /// Replaces the single admin with a set of approvers for sensitive actions
+    /// Once set, `upgrade`, `withdraw_fees` and changes to the set itself
+    /// only run through `propose_action` and `approve_action`
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `admins` - Approver addresses
+    /// * `threshold` - Distinct approvals needed to execute an action
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If an admin set is already configured (use `propose_action` instead)
+    /// - If `admins` lists an address twice
+    /// - If `threshold` is zero or larger than the number of admins
+    pub fn set_admins(
+        env: Env,
+        admin: Address,
+        admins: Vec<Address>,
+        threshold: u32,
+        reason: Symbol,
+    ) {
+        Self::require_admin(&env, &admin);
+        Self::require_single_admin(&env);
+        Self::record_audit(&env, &admin, "set_admins", &env.current_contract_address(), reason);
+        Self::store_admin_set(&env, &admins, threshold);
+    }
+
+    /// Retrieves the approver set and threshold for sensitive actions
+    /// Without a configured set, the single admin approves alone
+    pub fn get_admins(env: Env) -> (Vec<Address>, u32) {
+        (Self::approvers(&env), Self::approval_threshold(&env))
+    }
+
+    /// Proposes a sensitive action, counting as the proposer's approval
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `proposer` - Approver making the proposal
+    /// * `action` - The action to perform once approved
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Returns
+    /// The id of the new proposal
+    /// 
+    /// # Panics
+    /// - If the proposer is not an approver
+    /// - If a proposed admin set lists an address twice or has an unreachable threshold
+    pub fn propose_action(env: Env, proposer: Address, action: Action, reason: Symbol) -> u64 {
+        Self::require_approver(&env, &proposer);
+
+        // Validation: Reject an unusable admin set before anyone approves it
+        if let Action::SetAdmins(admins, threshold) = &action {
+            Self::check_admin_set(admins, *threshold);
+        }
+
+        let count: u64 = env.storage().instance().get(&AdminKey::ProposalCount).unwrap_or(0);
+        let proposal_id = count.checked_add(1).expect("arithmetic overflow");
+        env.storage().instance().set(&AdminKey::ProposalCount, &proposal_id);
+
+        let mut proposal = Proposal {
+            action,
+            approvals: Vec::new(&env),
+            executed: false,
+        };
+        proposal.approvals.push_back(proposer.clone());
+        Self::record_audit(&env, &proposer, "propose_action", &env.current_contract_address(), reason);
+        Self::settle_proposal(&env, proposal_id, proposal);
+
+        proposal_id
+    }
+
+    /// Approves a pending proposal, executing it once the threshold is met
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `approver` - Approver adding their approval
+    /// * `proposal_id` - Id of the proposal
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Returns
+    /// True if this approval caused the action to execute
+    /// 
+    /// # Panics
+    /// - If the caller is not an approver or has already approved
+    /// - If the proposal does not exist or was already executed
+    pub fn approve_action(env: Env, approver: Address, proposal_id: u64, reason: Symbol) -> bool {
+        Self::require_approver(&env, &approver);
+
+        let mut proposal = Self::get_proposal(env.clone(), proposal_id);
+        assert!(!proposal.executed, "Proposal already executed");
+        assert!(!proposal.approvals.contains(&approver), "Already approved");
+        proposal.approvals.push_back(approver.clone());
+        Self::record_audit(&env, &approver, "approve_action", &env.current_contract_address(), reason);
+
+        Self::settle_proposal(&env, proposal_id, proposal)
+    }
+
+    /// Retrieves an admin proposal by its id
+    /// 
+    /// # Panics
+    /// - If no proposal exists with the given id
+    pub fn get_proposal(env: Env, proposal_id: u64) -> Proposal {
+        env.storage()
+            .persistent()
+            .get(&AdminKey::Proposal(proposal_id))
+            .expect("Proposal not found")
+    }
+
+    /// Deletes refunded tips from storage to reclaim the space they occupy
+    /// The tips are removed along with their acknowledgements and refund
+    /// records, and disappear from their sender's and recipient's indexes.
+    /// Reclaimed and unsent tips count as refunded
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `ids` - Ids of the tips to delete
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Panics
+    /// - If any tip does not exist or has not been fully refunded
+    pub fn prune_refunded_tips(env: Env, admin: Address, ids: Vec<u64>, reason: Symbol) {
+        Self::require_admin(&env, &admin);
+        Self::record_audit(&env, &admin, "prune_tips", &env.current_contract_address(), reason);
+
+        for tip_id in ids.iter() {
+            let tip = Self::load_tip(&env, tip_id);
+
+            // Validation: Only tips whose funds have left the contract may go
+            let refunded_key = TipKey::Refunded(tip_id);
+            assert!(env.storage().persistent().has(&refunded_key), "Tip not refunded");
+
+            Self::remove_index_id(&env, &TipKey::SenderTips(tip.from), tip_id);
+            Self::remove_index_id(&env, &TipKey::RecipientTips(tip.to), tip_id);
+            env.storage().persistent().remove(&TipKey::Tip(tip_id));
+            env.storage().persistent().remove(&TipKey::Ack(tip_id));
+            env.storage().persistent().remove(&TipKey::RefundedAmount(tip_id));
+            env.storage().persistent().remove(&refunded_key);
+            if let Some(seq) = env.storage().persistent().get::<_, u64>(&TipKey::TipSeq(tip_id)) {
+                env.storage().persistent().remove(&TipKey::TipSeq(tip_id));
+                env.storage().persistent().remove(&TipKey::TipAt(seq));
+            }
+        }
+    }
+
+    /// Moves tips from the version 1 layout (one instance `Vec` under the
+    /// `tip` symbol) to per-id persistent keys, a batch at a time
+    /// Legacy tips keep ids 1 to n in their original order, and new tips are
+    /// refused until the last batch is done, so ids still increase with time.
+    /// Safe to call repeatedly: once the legacy vector is gone it does nothing
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `admin` - Address of the contract admin
+    /// * `cursor` - Number of legacy tips migrated by earlier calls (0 to start)
+    /// * `limit` - Most tips to migrate in this call
+    /// * `reason` - Reason code recorded in the audit log
+    /// 
+    /// # Returns
+    /// The number of tips migrated by this call; 0 once migration is complete
+    /// 
+    /// # Panics
+    /// - If `limit` is zero
+    /// - If `cursor` does not match the progress of earlier calls
+    pub fn migrate_tips(env: Env, admin: Address, cursor: u32, limit: u32, reason: Symbol) -> u32 {
+        Self::require_admin(&env, &admin);
+
+        let legacy_key = Symbol::new(&env, LEGACY_TIPS_KEY);
+        let legacy: Option<Vec<LegacyTip>> = env.storage().instance().get(&legacy_key);
+        let Some(legacy) = legacy else {
+            return 0;
+        };
+        assert!(limit > 0, "Limit must be greater than zero");
+        let progress: u32 = env.storage().instance().get(&ConfigKey::MigrationCursor).unwrap_or(0);
+        assert!(cursor == progress, "Unexpected migration cursor");
+        Self::record_audit(&env, &admin, "migrate_tips", &env.current_contract_address(), reason);
+
+        // Re-store the batch in order under ids following the earlier batches
+        let end = cursor.saturating_add(limit).min(legacy.len());
+        for index in cursor..end {
+            let old = legacy.get_unchecked(index);
+            let tip = Tip {
+                from: old.from,
+                to: old.to,
+                amount: old.amount,
+                message: old.message,
+                timestamp: old.timestamp,
+                token: old.token,
+                anonymous: false,
+                sponsor: None,
+                category: symbol_short!("none"),
+                public: true,
+            };
+            Self::store_tip_at(&env, index as u64 + 1, &tip);
+        }
+        env.storage().instance().set(&TipKey::TipCount, &(end as u64));
+
+        if end == legacy.len() {
+            env.storage().instance().remove(&legacy_key);
+            env.storage().instance().remove(&ConfigKey::MigrationCursor);
+            env.storage().instance().set(&ConfigKey::Version, &VERSION);
+        } else {
+            env.storage().instance().set(&ConfigKey::MigrationCursor, &end);
+        }
+
+        end - cursor
+    }
+
+    // ========================================================================
+    // INTERNAL HELPER FUNCTIONS
+    // ========================================================================
+
+    /// Appends an entry to the admin audit log
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `actor` - Admin that performed the action
+    /// * `action` - Name of the action
+    /// * `target` - User, token or contract the action applied to
+    /// * `reason` - Reason code supplied by the admin
+    fn record_audit(env: &Env, actor: &Address, action: &str, target: &Address, reason: Symbol) {
+        let count: u64 = env.storage().instance().get(&AdminKey::AuditCount).unwrap_or(0);
+        let id = count.checked_add(1).expect("arithmetic overflow");
+        env.storage().instance().set(&AdminKey::AuditCount, &id);
+
+        let entry = AuditEntry {
+            actor: actor.clone(),
+            action: Symbol::new(env, action),
+            target: target.clone(),
+            reason,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&AdminKey::AuditEntry(id), &entry);
+    }
+
+    /// Verifies that `admin` is the stored contract admin and has authorized the call
+    fn require_admin(env: &Env, admin: &Address) {
+        let stored: Address = env
+            .storage()
+            .instance()
+            .get(&ConfigKey::Admin)
+            .expect("Contract not initialized");
+        assert!(*admin == stored, "Caller is not the admin");
+        admin.require_auth();
+    }
+
+    /// Authorizes the admin or the configured oracle to settle conditional tips
+    fn require_condition_authorizer(env: &Env, authorizer: &Address) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ConfigKey::Admin)
+            .expect("Contract not initialized");
+        let oracle: Option<Address> = env.storage().instance().get(&ConfigKey::Oracle);
+        assert!(
+            *authorizer == admin || oracle.as_ref() == Some(authorizer),
+            "Caller is not the admin or oracle"
+        );
+        authorizer.require_auth();
+    }
+
+    /// Removes a pending conditional tip from storage so it can be settled once
+    fn take_conditional_tip(env: &Env, conditional_id: u64) -> ConditionalTip {
+        let key = TipKey::Conditional(conditional_id);
+        let conditional: ConditionalTip = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Conditional tip not found");
+        env.storage().persistent().remove(&key);
+        conditional
+    }
+
+    /// Panics if sensitive actions must go through multi-admin approval
+    fn require_single_admin(env: &Env) {
+        assert!(
+            !env.storage().instance().has(&AdminKey::Admins),
+            "Action requires multi-admin approval"
+        );
+    }
+
+    /// Panics unless an approver set lists each address once and its
+    /// threshold can be reached
+    fn check_admin_set(admins: &Vec<Address>, threshold: u32) {
+        for i in 0..admins.len() {
+            assert!(
+                admins.last_index_of(admins.get_unchecked(i)) == Some(i),
+                "Duplicate admin"
+            );
+        }
+        assert!(
+            threshold > 0 && threshold <= admins.len(),
+            "Invalid approval threshold"
+        );
+    }
+
+    /// Validates and saves the approver set and threshold
+    fn store_admin_set(env: &Env, admins: &Vec<Address>, threshold: u32) {
+        Self::check_admin_set(admins, threshold);
+        env.storage().instance().set(&AdminKey::Admins, admins);
+        env.storage().instance().set(&AdminKey::AdminThreshold, &threshold);
+    }
+
+    /// Addresses whose approvals count for sensitive actions
+    fn approvers(env: &Env) -> Vec<Address> {
+        env.storage().instance().get(&AdminKey::Admins).unwrap_or_else(|| {
+            let admin: Address = env
+                .storage()
+                .instance()
+                .get(&ConfigKey::Admin)
+                .expect("Contract not initialized");
+            Vec::from_array(env, [admin])
+        })
+    }
+
+    /// Distinct approvals a sensitive action needs (one without an admin set)
+    fn approval_threshold(env: &Env) -> u32 {
+        env.storage().instance().get(&AdminKey::AdminThreshold).unwrap_or(1)
+    }
+
+    /// Verifies the caller is an approver and authorized the call
+    fn require_approver(env: &Env, approver: &Address) {
+        assert!(Self::approvers(env).contains(approver), "Caller is not an admin");
+        approver.require_auth();
+    }
+
+    /// Stores a proposal, first executing it if it has enough approvals
+    /// Returns whether the action was executed
+    fn settle_proposal(env: &Env, proposal_id: u64, mut proposal: Proposal) -> bool {
+        let ready = proposal.approvals.len() >= Self::approval_threshold(env);
+        if ready {
+            proposal.executed = true;
+        }
+        env.storage().persistent().set(&AdminKey::Proposal(proposal_id), &proposal);
+
+        if ready {
+            match proposal.action {
+                Action::Upgrade(new_wasm_hash) => {
+                    env.deployer().update_current_contract_wasm(new_wasm_hash);
+                }
+                Action::WithdrawFees(token, destination) => {
+                    Self::pay_out_fees(env, &token, &destination);
+                }
+                Action::SetAdmins(admins, threshold) => {
+                    Self::store_admin_set(env, &admins, threshold);
+                }
+            }
+        }
+
+        ready
+    }
+
+    /// Transfers all accrued fees in a token and resets the fee bucket
+    fn pay_out_fees(env: &Env, token: &Address, destination: &Address) -> i128 {
+        let key = AccountKey::FeesCollected(token.clone());
+        let fees: i128 = env.storage().instance().get(&key).unwrap_or(0);
+        if fees > 0 {
+            env.storage().instance().set(&key, &0i128);
+            let token_client = TokenClient::new(env, token);
+            token_client.transfer(&env.current_contract_address(), destination, &fees);
+        }
+
+        env.events().publish(
+            (Symbol::new(env, "fees_withdrawn"), token.clone()),
+            (Self::next_event_seq(env), destination.clone(), fees),
+        );
+
+        fees
+    }
+
+    /// Reads the platform pause flag
+    fn paused(env: &Env) -> bool {
+        env.storage().instance().has(&ConfigKey::Paused)
+    }
+
+    /// Panics if the platform is paused
+    fn require_not_paused(env: &Env) {
+        assert!(!Self::paused(env), "Contract is paused");
+    }
+
+    /// Reads a feature flag, treating unset features as enabled
+    fn feature_enabled(env: &Env, feature: &Symbol) -> bool {
+        env.storage()
+            .instance()
+            .get::<_, Map<Symbol, bool>>(&ConfigKey::FeatureFlags)
+            .and_then(|flags| flags.get(feature.clone()))
+            .unwrap_or(true)
+    }
+
+    /// Panics if the named feature has been disabled
+    fn require_feature(env: &Env, feature: &str) {
+        assert!(
+            Self::feature_enabled(env, &Symbol::new(env, feature)),
+            "Feature disabled"
+        );
+    }
+
+    /// Sets the reentrancy guard, panicking if a guarded call is already running
+    /// A panic reverts the whole invocation, so the flag can never be left set
+    fn acquire_lock(env: &Env) {
+        assert!(!env.storage().instance().has(&ConfigKey::Lock), "reentrant call");
+        env.storage().instance().set(&ConfigKey::Lock, &true);
+    }
+
+    /// Clears the reentrancy guard at the end of a guarded call
+    fn release_lock(env: &Env) {
+        env.storage().instance().remove(&ConfigKey::Lock);
+    }
+
+    /// Applies the token allowlist, treating an empty allowlist as allow-all
+    fn token_allowed(env: &Env, token: &Address) -> bool {
+        let count: u32 = env.storage().instance().get(&ConfigKey::AllowedTokenCount).unwrap_or(0);
+        count == 0 || env.storage().instance().has(&ConfigKey::AllowedToken(token.clone()))
+    }
+
+    /// Pulls the configured flat platform fee from `payer`, if any
+    /// The payer's authorization is covered by the calling entrypoint;
+    /// fee-exempt payers are skipped
+    fn charge_fee(env: &Env, payer: &Address) {
+        if let Some((fee_token, fee_flat)) = Self::fee_for(env, payer) {
+            let fee_client = TokenClient::new(env, &fee_token);
+            fee_client.transfer(payer, &env.current_contract_address(), &fee_flat);
+            Self::accrue_fee(env, &fee_token, fee_flat);
+        }
+    }
+
+    /// Fee token and amount `payer` would be charged per tip, if anything
+    fn fee_for(env: &Env, payer: &Address) -> Option<(Address, i128)> {
+        if env.storage().persistent().has(&AdminKey::FeeExempt(payer.clone())) {
+            return None;
+        }
+        Self::standard_fee(env)
+    }
+
+    /// Fee token and amount charged per tip to payers without an exemption
+    fn standard_fee(env: &Env) -> Option<(Address, i128)> {
+        let config: FeeConfig = env.storage().instance().get(&ConfigKey::FeeConfig)?;
+        if config.fee_flat > 0 {
+            Some((config.fee_token, config.fee_flat))
+        } else {
+            None
+        }
+    }
+
+    /// Adds an amount to the withdrawable platform fee bucket for a token
+    fn accrue_fee(env: &Env, token: &Address, amount: i128) {
+        let key = AccountKey::FeesCollected(token.clone());
+        let fees: i128 = env.storage().instance().get(&key).unwrap_or(0);
+        let updated = fees.checked_add(amount).expect("arithmetic overflow");
+        env.storage().instance().set(&key, &updated);
+
+        // The lifetime total is never reduced by fee withdrawals
+        let lifetime_key = AccountKey::FeesLifetime(token.clone());
+        let lifetime: i128 = env.storage().instance().get(&lifetime_key).unwrap_or(0);
+        let lifetime = lifetime.checked_add(amount).expect("arithmetic overflow");
+        env.storage().instance().set(&lifetime_key, &lifetime);
+    }
+
+    /// Records a withdrawal for the cooldown, rejecting it if the user's
+    /// previous withdrawal was too recent
+    fn enforce_withdraw_cooldown(env: &Env, user: &Address) {
+        let cooldown: u64 = env.storage().instance().get(&ConfigKey::WithdrawCooldown).unwrap_or(0);
+        let key = AccountKey::LastWithdraw(user.clone());
+        let now = env.ledger().timestamp();
+
+        if cooldown > 0 {
+            if let Some(last) = env.storage().persistent().get::<_, u64>(&key) {
+                assert!(
+                    now >= last.saturating_add(cooldown),
+                    "Withdrawal cooldown has not elapsed"
+                );
+            }
+        }
+        env.storage().persistent().set(&key, &now);
+    }
+
+    /// Debits a user's available balance and pays it out to a destination
+    /// Internal function shared by withdraw and withdraw_to; callers are
+    /// responsible for authorization
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user whose balance is withdrawn
+    /// * `token` - Token contract address to withdraw
+    /// * `amount` - Amount to withdraw
+    /// * `destination` - Address that receives the tokens
+    fn process_withdrawal(
+        env: &Env,
+        user: &Address,
+        token: &Address,
+        amount: i128,
+        destination: &Address,
+    ) {
+        Self::acquire_lock(env);
+        Self::require_not_paused(env);
+
+        // Validation: Ensure amount is positive
+        assert!(amount > 0, "Withdrawal amount must be greater than zero");
+
+        // Validation: Frozen accounts cannot move funds out
+        assert!(
+            !env.storage().persistent().has(&AdminKey::Frozen(user.clone())),
+            "Account frozen"
+        );
+
+        // Validation: Enforce any destination allowlist configured for the token
+        if let Some(allowed) = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Address>>(&ConfigKey::WithdrawAllowlist(token.clone()))
+        {
+            assert!(
+                allowed.contains(destination),
+                "Destination not allowed for this token"
+            );
+        }
+
+        // Retrieve the user's current balance for this token
+        let balance_key = AccountKey::Balance(user.clone(), token.clone());
+        let mut balance: Balance = env
+            .storage()
+            .instance()
+            .get(&balance_key)
+            .expect("User has no balance to withdraw");
+
+        // Validation: Ensure user has sufficient available balance
+        assert!(
+            balance.available >= amount,
+            "Insufficient available balance for withdrawal"
+        );
+
+        // Reduce the available balance
+        balance.available -= amount;
+        // Track total withdrawn
+        balance.withdrawn = balance
+            .withdrawn
+            .checked_add(amount)
+            .expect("arithmetic overflow");
+
+        // Update the balance in storage
+        env.storage().instance().set(&balance_key, &balance);
+        Self::touch_balance(env, user, token);
+
+        // Withdrawing claims every tip received in this token so far
+        let latest: u64 = env.storage().instance().get(&TipKey::TipCount).unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&AccountKey::ClaimedThrough(user.clone(), token.clone()), &latest);
+
+        // The withdrawn amount is no longer owed to users
+        Self::adjust_liability(env, token, -amount);
+
+        // Keep the withdrawal fee, if one is configured, and pay out the rest
+        let fee_bps: u32 = env.storage().instance().get(&ConfigKey::WithdrawFeeBps).unwrap_or(0);
+        let fee = amount
+            .checked_mul(fee_bps as i128)
+            .expect("arithmetic overflow")
+            / BPS_DENOMINATOR;
+        if fee > 0 {
+            Self::accrue_fee(env, token, fee);
+        }
+        let payout = amount - fee;
+
+        // Record the withdrawal in the global ledger
+        let timestamp = env.ledger().timestamp();
+        Self::record_withdrawal(
+            env,
+            Withdrawal {
+                user: user.clone(),
+                token: token.clone(),
+                amount,
+                timestamp,
+                destination: destination.clone(),
+            },
+        );
+
+        // Create token client to handle the actual transfer
+        let token_client = TokenClient::new(env, token);
+
+        // Transfer the withdrawn amount from contract to the destination
+        let held_before = token_client.balance(&env.current_contract_address());
+        token_client.transfer(&env.current_contract_address(), destination, &payout);
+
+        // Validation: Non-standard tokens may report success without moving funds
+        let held_after = token_client.balance(&env.current_contract_address());
+        assert!(
+            held_before - held_after == payout,
+            "Token transfer amount mismatch"
+        );
+
+        // Emit withdrawal event for tracking
+        env.events().publish(
+            (Symbol::new(env, "withdrawal"),),
+            (Self::next_event_seq(env), user.clone(), token.clone(), amount, timestamp),
+        );
+
+        Self::release_lock(env);
+    }
+
+    /// Amount of a tip already returned through partial refunds
+    fn partially_refunded(env: &Env, tip_id: u64) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&TipKey::RefundedAmount(tip_id))
+            .unwrap_or(0)
+    }
+
+    /// Debits a refund from the recipient and sends it back to the tip's
+    /// real sender (even for anonymous tips), keeping any reversal fee
+    fn return_to_sender(env: &Env, tip_id: u64, tip: &Tip, amount: i128) {
+        // Take the amount back out of the recipient's balance
+        Self::update_balance(env, &tip.to, &tip.token, amount, false);
+
+        // Keep the reversal fee, if one is configured
+        let fee_bps: u32 = env.storage().instance().get(&ConfigKey::RefundFeeBps).unwrap_or(0);
+        let fee = amount
+            .checked_mul(fee_bps as i128)
+            .expect("arithmetic overflow")
+            / BPS_DENOMINATOR;
+        if fee > 0 {
+            Self::accrue_fee(env, &tip.token, fee);
+        }
+
+        let token_client = TokenClient::new(env, &tip.token);
+        token_client.transfer(&env.current_contract_address(), &tip.from, &(amount - fee));
+
+        let timestamp = env.ledger().timestamp();
+        env.events().publish(
+            (Symbol::new(env, "tip_refunded"),),
+            (Self::next_event_seq(env), tip_id, tip.to.clone(), amount, timestamp),
+        );
+    }
+
+    /// Builds a tip with default options, timestamped at the current ledger
+    fn new_tip(
+        env: &Env,
+        from: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        message: String,
+    ) -> Tip {
+        // Fill in the platform default when the sender left the message blank
+        let message = if message.is_empty() {
+            Self::default_message(env, amount).unwrap_or(message)
+        } else {
+            message
+        };
+
+        Tip {
+            from,
+            to,
+            amount,
+            message,
+            timestamp: env.ledger().timestamp(),
+            token,
+            anonymous: false,
+            sponsor: None,
+            category: symbol_short!("none"),
+            public: true,
+        }
+    }
+
+    /// Validates a tip, moves the funds and records it
+    /// Internal function shared by every send_tip variant; callers are
+    /// responsible for authorizing the sender
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip` - The tip to send
+    /// 
+    /// # Returns
+    /// The id assigned to the tip
+    fn process_tip(env: &Env, tip: Tip) -> u64 {
+        Self::acquire_lock(env);
+        Self::require_not_paused(env);
+        Self::validate_tip(env, &tip);
+        Self::enforce_rate_limit(env, &tip.from, 1);
+
+        // Get the token contract client to handle transfers
+        let token_client = TokenClient::new(env, &tip.token);
+
+        // Transfer the tip amount from sender to contract
+        token_client.transfer(&tip.from, &env.current_contract_address(), &tip.amount);
+
+        // Collect the platform fee separately so the recipient gets the full tip;
+        // a sponsor covers it when present
+        let fee_payer = tip.sponsor.clone().unwrap_or_else(|| tip.from.clone());
+        Self::charge_fee(env, &fee_payer);
+
+        let tip_id = Self::credit_tip(env, tip.clone());
+        Self::apply_match(env, tip_id, &tip);
+        Self::release_lock(env);
+        tip_id
+    }
+
+    /// Checks whether the recipient has claimed a tip by withdrawing since receiving it
+    fn is_claimed(env: &Env, tip_id: u64, tip: &Tip) -> bool {
+        let claimed_through: u64 = env
+            .storage()
+            .persistent()
+            .get(&AccountKey::ClaimedThrough(tip.to.clone(), tip.token.clone()))
+            .unwrap_or(0);
+        Self::tip_seq(env, tip_id) <= claimed_through
+    }
+
+    /// Reads the configured message length limit, falling back to the default
+    fn max_message_len(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&ConfigKey::MaxMessageLen)
+            .unwrap_or(DEFAULT_MAX_MESSAGE_LEN)
+    }
+
+    /// Reads the configured batch size limit, falling back to the default
+    fn max_batch(env: &Env) -> u32 {
+        env.storage().instance().get(&ConfigKey::MaxBatch).unwrap_or(MAX_BATCH)
+    }
+
+    /// Panics if a batch has more recipients than the configured limit
+    fn require_batch_size(env: &Env, len: u32) {
+        assert!(len <= Self::max_batch(env), "Too many recipients in batch");
+    }
+
+    /// Reads the minimum amount for a tip to carry a message (zero by default)
+    fn message_min_amount(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&ConfigKey::MessageMinAmount)
+            .unwrap_or(0)
+    }
+
+    /// Returns the configured default message if it may be attached to a tip
+    /// of `amount` under the current message limits
+    fn default_message(env: &Env, amount: i128) -> Option<String> {
+        let message: String = env.storage().instance().get(&ConfigKey::DefaultMessage)?;
+        if message.len() > Self::max_message_len(env) || amount < Self::message_min_amount(env) {
+            return None;
+        }
+        Some(message)
+    }
+
+    /// Funds a group of pre-validated tips with a single transfer and fee,
+    /// then records each one
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `from` - Address paying for the tips
+    /// * `token` - Token all the tips are sent in
+    /// * `total` - Sum of the tip amounts
+    /// * `tips` - The tips to record
+    /// 
+    /// # Returns
+    /// The ids assigned to the tips, in order
+    fn process_tips(env: &Env, from: &Address, token: &Address, total: i128, tips: Vec<Tip>) -> Vec<u64> {
+        Self::acquire_lock(env);
+        Self::enforce_rate_limit(env, from, tips.len());
+        let token_client = TokenClient::new(env, token);
+        token_client.transfer(from, &env.current_contract_address(), &total);
+        Self::charge_fee(env, from);
+
+        let mut ids = Vec::new(env);
+        for tip in tips.iter() {
+            let tip_id = Self::credit_tip(env, tip.clone());
+            Self::apply_match(env, tip_id, &tip);
+            ids.push_back(tip_id);
+        }
+        Self::release_lock(env);
+
+        ids
+    }
+
+    /// Counts tips against their sender's rate limit, starting a fresh window
+    /// once the previous one has elapsed
+    /// Batch and split tips count once per resulting tip
+    /// 
+    /// # Panics
+    /// - If the tips do not all fit in what is left of the current window
+    fn enforce_rate_limit(env: &Env, sender: &Address, tips: u32) {
+        let Some((max_tips, window_secs)) = env
+            .storage()
+            .instance()
+            .get::<_, (u32, u64)>(&ConfigKey::RateLimitConfig)
+        else {
+            return;
+        };
+
+        let now = env.ledger().timestamp();
+        let key = AccountKey::RateLimit(sender.clone());
+        let (mut window_start, mut count): (u64, u32) =
+            env.storage().persistent().get(&key).unwrap_or((now, 0));
+
+        // Roll over to a new window once the old one has passed
+        if now >= window_start.saturating_add(window_secs) {
+            window_start = now;
+            count = 0;
+        }
+
+        let count = count.checked_add(tips).expect("arithmetic overflow");
+        assert!(count <= max_tips, "rate limit exceeded");
+        env.storage().persistent().set(&key, &(window_start, count));
+    }
+
+    /// Reads a recipient's receive window in a token as (window start,
+    /// amount received), starting a fresh window once the old one has passed
+    fn receive_window(env: &Env, recipient: &Address, token: &Address, window_secs: u64) -> (u64, i128) {
+        let now = env.ledger().timestamp();
+        let key = AccountKey::ReceivedInWindow(recipient.clone(), token.clone());
+        let (window_start, received): (u64, i128) =
+            env.storage().persistent().get(&key).unwrap_or((now, 0));
+        if now >= window_start.saturating_add(window_secs) {
+            (now, 0)
+        } else {
+            (window_start, received)
+        }
+    }
+
+    /// Counts a validated tip against its recipient's receive cap, if they set one
+    /// Recorded at validation so later entries of the same batch see it
+    fn record_received(env: &Env, tip: &Tip) {
+        let Some((_, window_secs)) = env
+            .storage()
+            .persistent()
+            .get::<_, (i128, u64)>(&AccountKey::ReceiveCap(tip.to.clone(), tip.token.clone()))
+        else {
+            return;
+        };
+
+        let (window_start, received) = Self::receive_window(env, &tip.to, &tip.token, window_secs);
+        let received = received.checked_add(tip.amount).expect("arithmetic overflow");
+        env.storage().persistent().set(
+            &AccountKey::ReceivedInWindow(tip.to.clone(), tip.token.clone()),
+            &(window_start, received),
+        );
+    }
+
+    /// Takes a tip back out of its recipient's receive window
+    /// Tips counted in an earlier window have already rolled off
+    fn unrecord_received(env: &Env, tip: &Tip) {
+        let key = AccountKey::ReceivedInWindow(tip.to.clone(), tip.token.clone());
+        let Some((window_start, received)) = env.storage().persistent().get::<_, (u64, i128)>(&key) else {
+            return;
+        };
+        if tip.timestamp < window_start {
+            return;
+        }
+        let received = received.checked_sub(tip.amount).expect("arithmetic overflow").max(0);
+        env.storage().persistent().set(&key, &(window_start, received));
+    }
+
+    /// Checks the rules every tip must satisfy before any funds move, and
+    /// counts the tip against its recipient's receive cap
+    fn validate_tip(env: &Env, tip: &Tip) {
+        if let Err(reason) = Self::check_tip(env, tip) {
+            panic!("{}", reason);
+        }
+        Self::record_received(env, tip);
+    }
+
+    /// Checks that a token may currently be paid into the contract
+    fn check_token(env: &Env, token: &Address) -> Result<(), &'static str> {
+        // Validation: Only approved tokens may be tipped
+        if !Self::token_allowed(env, token) {
+            return Err("Token is not allowed");
+        }
+
+        // Validation: A token can be paused on its own, e.g. if its integration breaks
+        if env.storage().instance().has(&ConfigKey::TokenPaused(token.clone())) {
+            return Err("Token is paused");
+        }
+
+        // Validation: Fail clearly up front if the token is not a token contract
+        // (e.g. a classic account), rather than part-way through the transfer
+        let token_client = TokenClient::new(env, token);
+        if !matches!(token_client.try_decimals(), Ok(Ok(_))) {
+            return Err("invalid token");
+        }
+
+        Ok(())
+    }
+
+    /// Checks the amount, message and token rules shared by every payment in
+    /// a tip, whether to a recipient or a pool
+    fn check_payment(env: &Env, token: &Address, amount: i128, message: &String) -> Result<(), &'static str> {
+        // Validation: Ensure amount is positive
+        if amount <= 0 {
+            return Err("Tip amount must be greater than zero");
+        }
+
+        // Validation: Ensure message is not excessively long
+        if message.len() > Self::max_message_len(env) {
+            return Err("Message exceeds maximum length");
+        }
+
+        // Validation: Messages cost storage, so small tips may not carry one
+        if !message.is_empty() && amount < Self::message_min_amount(env) {
+            return Err("Tip amount too small to include a message");
+        }
+
+        Self::check_token(env, token)
+    }
+
+    /// Evaluates the tip rules without panicking, for callers that skip
+    /// invalid tips instead of aborting
+    /// 
+    /// # Returns
+    /// The reason the tip is invalid, if it is
+    fn check_tip(env: &Env, tip: &Tip) -> Result<(), &'static str> {
+        Self::check_payment(env, &tip.token, tip.amount, &tip.message)?;
+
+        // Validation: Prevent self-tipping unless the admin has allowed it
+        if tip.from == tip.to && !env.storage().instance().has(&ConfigKey::AllowSelfTip) {
+            return Err("Cannot send a tip to yourself");
+        }
+
+        // Validation: Funds credited to the contract itself could never be withdrawn
+        if tip.to == env.current_contract_address() {
+            return Err("cannot tip the contract");
+        }
+
+        // Validation: Closed platforms only accept tips to onboarded users
+        if env.storage().instance().has(&ConfigKey::RequireRegistration)
+            && !env.storage().persistent().has(&AdminKey::Registered(tip.to.clone()))
+        {
+            return Err("Recipient is not registered");
+        }
+
+        // Validation: Respect the recipient's own limit on what they accept
+        if let Some((cap, window_secs)) = env
+            .storage()
+            .persistent()
+            .get::<_, (i128, u64)>(&AccountKey::ReceiveCap(tip.to.clone(), tip.token.clone()))
+        {
+            let (_, received) = Self::receive_window(env, &tip.to, &tip.token, window_secs);
+            if received.checked_add(tip.amount).is_none_or(|total| total > cap) {
+                return Err("recipient cap exceeded");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records a tip whose funds are already held by the contract
+    /// Stores it, credits the recipient, updates both profiles and emits
+    /// the tip_sent event
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip` - The funded tip
+    /// 
+    /// # Returns
+    /// The id assigned to the tip
+    fn credit_tip(env: &Env, tip: Tip) -> u64 {
+        // Store the tip under a fresh id and index it for both parties
+        let tip_id = Self::store_tip(env, &tip);
+
+        // Update the recipient's balance
+        Self::update_balance(env, &tip.to, &tip.token, tip.amount, true);
+
+        // Update user profiles for statistics tracking; an anonymous tip
+        // leaves no trace in the sender's public stats
+        if !tip.anonymous {
+            Self::update_sender_profile(env, &tip.from, tip.amount);
+        }
+        Self::update_recipient_profile(env, &tip.to, tip.amount);
+        Self::update_retention(env, &tip.to, &tip.from);
+        // Private tips stay out of the public rankings
+        if tip.public {
+            Self::record_token_volume(env, &tip.token, tip.amount);
+            Self::update_leaderboard(env, &tip.to, &tip.token, tip.amount);
+        }
+        if tip.public && !tip.anonymous {
+            Self::record_pair_volume(env, &tip.from, &tip.to, tip.amount);
+        }
+        env.storage()
+            .persistent()
+            .set(&TipKey::LastTip(tip.from.clone()), &tip_id);
+
+        // Emit an event (if using Soroban event system)
+        // This allows off-chain listeners to track tips in real-time; the
+        // token and recipient topics let indexers subscribe to just those
+        let public = Self::public_view(env, tip);
+        env.events().publish(
+            (symbol_short!("tip"), public.token.clone(), public.to.clone()),
+            (Self::next_event_seq(env), public.from, public.to, public.amount, public.timestamp),
+        );
+
+        // Return the tip ID for future reference
+        tip_id
+    }
+
+    /// Allocates the sequence number for the next published event
+    /// Numbers start at 1 and increase by one per event, across all event types
+    fn next_event_seq(env: &Env) -> u64 {
+        let seq: u64 = env.storage().instance().get(&ConfigKey::EventSeq).unwrap_or(0);
+        let next = seq.checked_add(1).expect("arithmetic overflow");
+        env.storage().instance().set(&ConfigKey::EventSeq, &next);
+        next
+    }
+
+    /// Returns the publicly visible form of a tip
+    /// Anonymous tips have their sender replaced by the sentinel address
+    fn public_view(env: &Env, mut tip: Tip) -> Tip {
+        if tip.anonymous {
+            tip.from = Address::from_string(&String::from_str(env, ANONYMOUS_SENDER));
+        }
+        tip
+    }
+
+    /// Stores a withdrawal under the next withdrawal id
+    fn record_withdrawal(env: &Env, withdrawal: Withdrawal) -> u64 {
+        let count: u64 = env.storage().instance().get(&AccountKey::WithdrawalCount).unwrap_or(0);
+        let id = count.checked_add(1).expect("arithmetic overflow");
+        env.storage().instance().set(&AccountKey::WithdrawalCount, &id);
+        env.storage().persistent().set(&AccountKey::Withdrawal(id), &withdrawal);
+        Self::push_index_id(env, &AccountKey::UserWithdrawals(withdrawal.user.clone()), id);
+        id
+    }
+
+    /// Persists a new tip and appends it to the sender and recipient indexes
+    /// Internal function called by send_tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `tip` - The tip to record
+    /// 
+    /// # Returns
+    /// The id assigned to the tip (ids start at 1)
+    fn store_tip(env: &Env, tip: &Tip) -> u64 {
+        // Validation: Legacy tips must take the lowest ids before new ones are added
+        assert!(
+            !env.storage().instance().has(&Symbol::new(env, LEGACY_TIPS_KEY)),
+            "Tip migration pending"
+        );
+
+        // Allocate the next sequence number from the global counter
+        let count: u64 = env.storage().instance().get(&TipKey::TipCount).unwrap_or(0);
+        let seq = count.checked_add(1).expect("arithmetic overflow");
+        env.storage().instance().set(&TipKey::TipCount, &seq);
+
+        let tip_id = Self::allocate_tip_id(env, tip, seq);
+        Self::store_tip_at(env, tip_id, tip);
+        tip_id
+    }
+
+    /// Picks the id for the tip with sequence number `seq`
+    /// In hashed id mode the id comes from hashing the tip's parties,
+    /// timestamp and sequence number, tagged with `HASHED_ID_FLAG`; otherwise,
+    /// for anonymous tips, or when the hashed id is taken, it is `seq` itself
+    fn allocate_tip_id(env: &Env, tip: &Tip, seq: u64) -> u64 {
+        if tip.anonymous || !env.storage().instance().has(&ConfigKey::HashedTipIds) {
+            return seq;
+        }
+
+        let tip_id = Self::hashed_tip_id(env, &tip.from, &tip.to, tip.timestamp, seq);
+        if env.storage().persistent().has(&TipKey::Tip(tip_id)) {
+            return seq;
+        }
+        env.storage().persistent().set(&TipKey::TipAt(seq), &tip_id);
+        env.storage().persistent().set(&TipKey::TipSeq(tip_id), &seq);
+        tip_id
+    }
+
+    /// Derives a hashed tip id from the first eight bytes of the hash
+    fn hashed_tip_id(env: &Env, from: &Address, to: &Address, timestamp: u64, seq: u64) -> u64 {
+        let preimage = (from.clone(), to.clone(), timestamp, seq);
+        let digest: BytesN<32> = env.crypto().sha256(&preimage.to_xdr(env)).into();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&digest.to_array()[..8]);
+        u64::from_be_bytes(bytes) | HASHED_ID_FLAG
+    }
+
+    /// Id of the tip with a given sequence number
+    fn tip_id_at(env: &Env, seq: u64) -> u64 {
+        env.storage().persistent().get(&TipKey::TipAt(seq)).unwrap_or(seq)
+    }
+
+    /// Sequence number of a tip, which is its id unless the id is hashed
+    fn tip_seq(env: &Env, tip_id: u64) -> u64 {
+        if tip_id & HASHED_ID_FLAG == 0 {
+            return tip_id;
+        }
+        env.storage()
+            .persistent()
+            .get(&TipKey::TipSeq(tip_id))
+            .expect("Tip not found")
+    }
+
+    /// Stores a tip under a given id and adds it to both parties' indexes
+    fn store_tip_at(env: &Env, tip_id: u64, tip: &Tip) {
+        // Store the tip itself under its own key
+        env.storage().persistent().set(&TipKey::Tip(tip_id), tip);
+
+        // Index the tip for both parties; anonymous tips stay out of the
+        // sender's index so it cannot unmask them
+        if !tip.anonymous {
+            Self::push_index_id(env, &TipKey::SenderTips(tip.from.clone()), tip_id);
+        }
+        Self::push_index_id(env, &TipKey::RecipientTips(tip.to.clone()), tip_id);
+    }
+
+    /// Hashes the public view of a stored tip, bound to its id and this contract
+    fn tip_hash(env: &Env, tip_id: u64) -> BytesN<32> {
+        let tip = Self::public_view(env, Self::load_tip(env, tip_id));
+        let preimage = (env.current_contract_address(), tip_id, tip);
+        env.crypto().sha256(&preimage.to_xdr(env)).into()
+    }
+
+    /// Loads a pool by id, panicking if it does not exist
+    fn load_pool(env: &Env, pool_id: u64) -> Pool {
+        env.storage()
+            .persistent()
+            .get(&TipKey::Pool(pool_id))
+            .expect("Pool not found")
+    }
+
+    /// Loads a tip by id, panicking if it does not exist
+    fn load_tip(env: &Env, tip_id: u64) -> Tip {
+        env.storage()
+            .persistent()
+            .get(&TipKey::Tip(tip_id))
+            .expect("Tip not found")
+    }
+
+    /// Reads a tip or withdrawal id index, returning an empty list if it was never written
+    fn index_ids<K: IntoVal<Env, Val>>(env: &Env, key: &K) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(key)
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Appends an id to an index
+    fn push_index_id<K: IntoVal<Env, Val>>(env: &Env, key: &K, id: u64) {
+        let mut ids = Self::index_ids(env, key);
+        ids.push_back(id);
+        env.storage().persistent().set(key, &ids);
+    }
+
+    /// Removes an id from an index, if present
+    fn remove_index_id<K: IntoVal<Env, Val>>(env: &Env, key: &K, id: u64) {
+        let mut ids = Self::index_ids(env, key);
+        if let Some(index) = ids.first_index_of(id) {
+            ids.remove(index);
+            env.storage().persistent().set(key, &ids);
+        }
+    }
+
+    /// Resolves a page of tips from an id index
+    /// Skips the first `start` tips accepted by `filter`, then collects up to
+    /// `limit` more (never more than MAX_PAGE_SIZE). Returned tips are in
+    /// their public form
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `ids` - Tip ids to walk, in the order results should appear
+    /// * `start` - Number of matching tips to skip
+    /// * `limit` - Maximum number of tips to return
+    /// * `filter` - Predicate selecting which tips count as matches
+    fn page_tips<F>(env: &Env, ids: &Vec<u64>, start: u32, limit: u32, filter: F) -> Vec<Tip>
+    where
+        F: Fn(&Tip) -> bool,
+    {
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let mut page = Vec::new(env);
+        let mut skipped = 0;
+
+        for id in ids.iter() {
+            if page.len() >= limit {
+                break;
+            }
+            let tip = Self::load_tip(env, id);
+            if !filter(&tip) {
+                continue;
+            }
+            if skipped < start {
+                skipped += 1;
+                continue;
+            }
+            page.push_back(Self::public_view(env, tip));
+        }
+
+        page
+    }
+
+    /// Updates a user's balance when they receive or give back a tip
+    /// Internal function called by send_tip and refund_tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user receiving the tip
+    /// * `token` - Token contract address
+    /// * `amount` - Amount to add to the balance
+    /// * `is_deposit` - Whether this is a deposit (true) or withdrawal (false)
+    fn update_balance(env: &Env, user: &Address, token: &Address, amount: i128, is_deposit: bool) {
+        // Construct the storage key for this balance
+        let balance_key = AccountKey::Balance(user.clone(), token.clone());
+
+        // Register the token for the user the first time a balance is created
+        if !env.storage().instance().has(&balance_key) {
+            Self::add_user_token(env, user, token);
+        }
+
+        // Retrieve existing balance or create a new one
+        let mut balance: Balance = env
+            .storage()
+            .instance()
+            .get(&balance_key)
+            .unwrap_or_else(|| Balance {
+                total_received: 0,
+                available: 0,
+                withdrawn: 0,
+                token: token.clone(),
+            });
+
+        // Update balance values
+        if is_deposit {
+            balance.total_received = balance
+                .total_received
+                .checked_add(amount)
+                .expect("arithmetic overflow");
+            balance.available = balance
+                .available
+                .checked_add(amount)
+                .expect("arithmetic overflow");
+        } else {
+            assert!(balance.available >= amount, "Insufficient available balance");
+            balance.available -= amount;
+        }
+
+        // Save the updated balance to storage
+        env.storage().instance().set(&balance_key, &balance);
+        Self::touch_balance(env, user, token);
+
+        if is_deposit {
+            Self::check_balance_alert(env, user, token, balance.available);
+            Self::check_goal(env, user, token, balance.total_received);
+        }
+
+        // Keep the platform-wide liability in step with available balances
+        if is_deposit {
+            Self::adjust_liability(env, token, amount);
+        } else {
+            Self::adjust_liability(env, token, -amount);
+        }
+    }
+
+    /// Adds a user's own deposit to their available balance
+    /// Unlike a received tip it leaves total_received, goals and alerts alone
+    fn add_available(env: &Env, user: &Address, token: &Address, amount: i128) {
+        let balance_key = AccountKey::Balance(user.clone(), token.clone());
+        if !env.storage().instance().has(&balance_key) {
+            Self::add_user_token(env, user, token);
+        }
+
+        let mut balance: Balance = env
+            .storage()
+            .instance()
+            .get(&balance_key)
+            .unwrap_or_else(|| Balance {
+                total_received: 0,
+                available: 0,
+                withdrawn: 0,
+                token: token.clone(),
+            });
+        balance.available = balance
+            .available
+            .checked_add(amount)
+            .expect("arithmetic overflow");
+        env.storage().instance().set(&balance_key, &balance);
+        Self::touch_balance(env, user, token);
+
+        Self::adjust_liability(env, token, amount);
+    }
+
+    /// Records that a balance was just credited or debited, for dormancy
+    fn touch_balance(env: &Env, user: &Address, token: &Address) {
+        env.storage().persistent().set(
+            &AccountKey::LastActivity(user.clone(), token.clone()),
+            &env.ledger().timestamp(),
+        );
+    }
+
+    /// Fires a user's balance alert the first time their available balance
+    /// reaches its threshold
+    fn check_balance_alert(env: &Env, user: &Address, token: &Address, available: i128) {
+        let key = AccountKey::Alert(user.clone(), token.clone());
+        let Some(mut alert) = env.storage().persistent().get::<_, BalanceAlert>(&key) else {
+            return;
+        };
+        if alert.triggered || available < alert.threshold {
+            return;
+        }
+
+        alert.triggered = true;
+        env.storage().persistent().set(&key, &alert);
+        env.events().publish(
+            (Symbol::new(env, "threshold_reached"), user.clone()),
+            (Self::next_event_seq(env), token.clone(), alert.threshold, available),
+        );
+    }
+
+    /// Marks a user's goal complete, with a `goal_reached` event, the first
+    /// time their total received reaches its target
+    fn check_goal(env: &Env, user: &Address, token: &Address, total_received: i128) {
+        let key = AccountKey::Goal(user.clone(), token.clone());
+        let Some(mut goal) = env.storage().persistent().get::<_, Goal>(&key) else {
+            return;
+        };
+        if goal.completed || total_received < goal.target {
+            return;
+        }
+
+        goal.completed = true;
+        env.storage().persistent().set(&key, &goal);
+        env.events().publish(
+            (Symbol::new(env, "goal_reached"), user.clone()),
+            (Self::next_event_seq(env), token.clone(), goal.target, total_received),
+        );
+    }
+
+    /// Adds a token to the set of tokens a user holds balances in
+    fn add_user_token(env: &Env, user: &Address, token: &Address) {
+        let key = AccountKey::UserTokens(user.clone());
+        let mut tokens: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| Vec::new(env));
+        if !tokens.contains(token) {
+            tokens.push_back(token.clone());
+            env.storage().persistent().set(&key, &tokens);
+        }
+    }
+
+    /// Removes a token from the set of tokens a user holds balances in
+    fn remove_user_token(env: &Env, user: &Address, token: &Address) {
+        let key = AccountKey::UserTokens(user.clone());
+        let Some(mut tokens) = env.storage().persistent().get::<_, Vec<Address>>(&key) else {
+            return;
+        };
+        if let Some(index) = tokens.first_index_of(token) {
+            tokens.remove(index);
+        }
+        if tokens.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &tokens);
+        }
+    }
+
+    /// Applies a change to the total amount owed to users for a token
+    /// Positive deltas record new credits, negative deltas record payouts
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    /// * `delta` - Signed change to the liability
+    fn adjust_liability(env: &Env, token: &Address, delta: i128) {
+        let key = AccountKey::TotalLiability(token.clone());
+        let liability: i128 = env.storage().instance().get(&key).unwrap_or(0);
+        let updated = liability.checked_add(delta).expect("arithmetic overflow");
+        env.storage().instance().set(&key, &updated);
+    }
+
+    /// Calculates everything the contract owes in a token
+    /// Internal function shared by the solvency views
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    fn total_owed(env: &Env, token: &Address) -> i128 {
+        let liability: i128 = env
+            .storage()
+            .instance()
+            .get(&AccountKey::TotalLiability(token.clone()))
+            .unwrap_or(0);
+        let fees: i128 = env
+            .storage()
+            .instance()
+            .get(&AccountKey::FeesCollected(token.clone()))
+            .unwrap_or(0);
+        let escrow: i128 = env
+            .storage()
+            .instance()
+            .get(&TipKey::TotalEscrow(token.clone()))
+            .unwrap_or(0);
+        let charity: i128 = env
+            .storage()
+            .instance()
+            .get(&AccountKey::CharityPool(token.clone()))
+            .unwrap_or(0);
+        liability
+            .checked_add(fees)
+            .and_then(|owed| owed.checked_add(escrow))
+            .and_then(|owed| owed.checked_add(charity))
+            .expect("arithmetic overflow")
+    }
+
+    /// Adjusts the total held in escrow for a token
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `token` - Token contract address
+    /// * `delta` - Signed change to the escrowed total
+    fn adjust_escrow(env: &Env, token: &Address, delta: i128) {
+        let key = TipKey::TotalEscrow(token.clone());
+        let escrow: i128 = env.storage().instance().get(&key).unwrap_or(0);
+        let updated = escrow.checked_add(delta).expect("arithmetic overflow");
+        env.storage().instance().set(&key, &updated);
+    }
+
+    /// Pays out a recipient's match pledge against a freshly credited tip
+    /// The match is the tip amount, limited by what is left of the pledge;
+    /// an exhausted pledge is removed
+    fn apply_match(env: &Env, tip_id: u64, tip: &Tip) {
+        let key = TipKey::Match(tip.to.clone(), tip.token.clone());
+        let Some(mut pledge) = env.storage().persistent().get::<_, MatchPledge>(&key) else {
+            return;
+        };
+
+        let matched = tip.amount.min(pledge.remaining);
+        pledge.remaining -= matched;
+        if pledge.remaining == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &pledge);
+        }
+
+        // Move the matched amount out of escrow and into the recipient's balance
+        Self::adjust_escrow(env, &tip.token, -matched);
+        Self::update_balance(env, &tip.to, &tip.token, matched, true);
+        env.storage()
+            .persistent()
+            .set(&TipKey::Matched(tip_id), &(pledge.sponsor.clone(), matched));
+
+        env.events().publish(
+            (Symbol::new(env, "tip_matched"), tip.to.clone()),
+            (Self::next_event_seq(env), tip_id, pledge.sponsor, matched),
+        );
+    }
+
+    /// Takes back the share of a tip's match that belongs to a reversed part
+    /// of the tip
+    /// The share is proportional to `amount` out of the `outstanding` part of
+    /// the tip. It goes back into the sponsor's pledge while that is still
+    /// active, and straight back to the sponsor otherwise
+    /// 
+    /// # Returns
+    /// The matched amount taken back from the recipient
+    fn reverse_match(env: &Env, tip_id: u64, tip: &Tip, amount: i128, outstanding: i128) -> i128 {
+        let matched_key = TipKey::Matched(tip_id);
+        let Some((sponsor, matched)) = env.storage().persistent().get::<_, (Address, i128)>(&matched_key)
+        else {
+            return 0;
+        };
+
+        let share = if amount >= outstanding {
+            matched
+        } else {
+            matched.checked_mul(amount).expect("arithmetic overflow") / outstanding
+        };
+        if share == matched {
+            env.storage().persistent().remove(&matched_key);
+        } else {
+            env.storage()
+                .persistent()
+                .set(&matched_key, &(sponsor.clone(), matched - share));
+        }
+        if share == 0 {
+            return 0;
+        }
+        Self::update_balance(env, &tip.to, &tip.token, share, false);
+
+        let pledge_key = TipKey::Match(tip.to.clone(), tip.token.clone());
+        match env.storage().persistent().get::<_, MatchPledge>(&pledge_key) {
+            Some(mut pledge) if pledge.sponsor == sponsor => {
+                pledge.remaining = pledge.remaining.checked_add(share).expect("arithmetic overflow");
+                env.storage().persistent().set(&pledge_key, &pledge);
+                Self::adjust_escrow(env, &tip.token, share);
+            }
+            _ => {
+                let token_client = TokenClient::new(env, &tip.token);
+                token_client.transfer(&env.current_contract_address(), &sponsor, &share);
+            }
+        }
+
+        env.events().publish(
+            (Symbol::new(env, "match_reversed"), tip.to.clone()),
+            (Self::next_event_seq(env), tip_id, sponsor, share),
+        );
+        share
+    }
+
+    /// Adds a tip's amount to its token's volume, keeping the list sorted
+    /// Tokens beyond the tracking bound are ignored
+    fn record_token_volume(env: &Env, token: &Address, amount: i128) {
+        let mut list: Vec<(Address, i128)> = env
+            .storage()
+            .instance()
+            .get(&StatsKey::TokenVolumeList)
+            .unwrap_or_else(|| Vec::new(env));
+
+        // Find the token's entry, adding one if there is still room
+        let mut index = match list.iter().position(|(entry, _)| entry == *token) {
+            Some(index) => index as u32,
+            None => {
+                if list.len() >= MAX_TRACKED_TOKENS {
+                    return;
+                }
+                list.push_back((token.clone(), 0));
+                list.len() - 1
+            }
+        };
+        let (_, volume) = list.get_unchecked(index);
+        let volume = volume.checked_add(amount).expect("arithmetic overflow");
+        list.set(index, (token.clone(), volume));
+
+        // Move the entry up past any tokens it has overtaken
+        while index > 0 && list.get_unchecked(index - 1).1 < volume {
+            let above = list.get_unchecked(index - 1);
+            list.set(index, above);
+            list.set(index - 1, (token.clone(), volume));
+            index -= 1;
+        }
+
+        // An unsent tip can instead drop the entry below tokens it had passed
+        while index + 1 < list.len() && list.get_unchecked(index + 1).1 > volume {
+            let below = list.get_unchecked(index + 1);
+            list.set(index, below);
+            list.set(index + 1, (token.clone(), volume));
+            index += 1;
+        }
+
+        env.storage().instance().set(&StatsKey::TokenVolumeList, &list);
+    }
+
+    /// Reads a token's recipient leaderboard, empty if nobody was tipped yet
+    fn leaderboard(env: &Env, token: &Address) -> Vec<(Address, i128)> {
+        env.storage()
+            .instance()
+            .get(&StatsKey::Leaderboard(token.clone()))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Adds to a recipient's public total and re-ranks them on the token's leaderboard
+    /// When the leaderboard is full, the lowest entry drops off
+    fn update_leaderboard(env: &Env, user: &Address, token: &Address, delta: i128) {
+        let key = StatsKey::PublicReceived(user.clone(), token.clone());
+        let total: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        let total = total.checked_add(delta).expect("arithmetic overflow");
+        env.storage().persistent().set(&key, &total);
+
+        let mut list = Self::leaderboard(env, token);
+
+        // Take out the user's old entry, then insert it at its new place
+        if let Some(index) = list.iter().position(|(entry, _)| entry == *user) {
+            list.remove(index as u32);
+        }
+        let index = list
+            .iter()
+            .position(|(_, received)| received < total)
+            .map(|index| index as u32)
+            .unwrap_or(list.len());
+
+        // A recipient whose public total fell to zero after an unsend leaves the board
+        if total > 0 && index < MAX_LEADERBOARD_SIZE {
+            list.insert(index, (user.clone(), total));
+            if list.len() > MAX_LEADERBOARD_SIZE {
+                list.pop_back();
+            }
+        }
+
+        env.storage()
+            .instance()
+            .set(&StatsKey::Leaderboard(token.clone()), &list);
+    }
+
+    /// Adds a tip to its sender-recipient pair volume and updates the ranking
+    /// When the ranking is full, a pair only enters by beating the lowest entry
+    fn record_pair_volume(env: &Env, from: &Address, to: &Address, amount: i128) {
+        let key = StatsKey::PairVolume(from.clone(), to.clone());
+        let volume: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        let volume = volume.checked_add(amount).expect("arithmetic overflow");
+        env.storage().persistent().set(&key, &volume);
+
+        let mut list: Vec<(Address, Address, i128)> = env
+            .storage()
+            .instance()
+            .get(&StatsKey::TopPairs)
+            .unwrap_or_else(|| Vec::new(env));
+
+        // Find the pair's slot, taking the last place if it now outranks it
+        let existing = list
+            .iter()
+            .position(|(entry_from, entry_to, _)| entry_from == *from && entry_to == *to);
+        let mut index = match existing {
+            // A pair whose only volume was unsent drops out of the ranking
+            Some(index) if volume <= 0 => {
+                list.remove(index as u32);
+                env.storage().instance().set(&StatsKey::TopPairs, &list);
+                return;
+            }
+            Some(index) => index as u32,
+            None if volume <= 0 => return,
+            None if list.len() < MAX_TRACKED_PAIRS => {
+                list.push_back((from.clone(), to.clone(), volume));
+                list.len() - 1
+            }
+            None => {
+                let last = list.len() - 1;
+                if list.get_unchecked(last).2 >= volume {
+                    return;
+                }
+                last
+            }
+        };
+        list.set(index, (from.clone(), to.clone(), volume));
+
+        // Move the entry up past any pairs it has overtaken
+        while index > 0 && list.get_unchecked(index - 1).2 < volume {
+            let above = list.get_unchecked(index - 1);
+            list.set(index, above);
+            list.set(index - 1, (from.clone(), to.clone(), volume));
+            index -= 1;
+        }
+
+        // An unsent tip can instead drop the entry below pairs it had passed
+        while index + 1 < list.len() && list.get_unchecked(index + 1).2 > volume {
+            let below = list.get_unchecked(index + 1);
+            list.set(index, below);
+            list.set(index + 1, (from.clone(), to.clone(), volume));
+            index += 1;
+        }
+
+        env.storage().instance().set(&StatsKey::TopPairs, &list);
+    }
+
+    /// Records another tip from `supporter` in the recipient's retention counts
+    /// A supporter moves from one-time to repeat on their second tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `recipient` - Address of the tip recipient
+    /// * `supporter` - Address of the tip sender
+    fn update_retention(env: &Env, recipient: &Address, supporter: &Address) {
+        let count_key = StatsKey::SupporterTips(recipient.clone(), supporter.clone());
+        let count: u32 = env.storage().persistent().get(&count_key).unwrap_or(0);
+        env.storage().persistent().set(&count_key, &count.saturating_add(1));
+
+        let retention_key = StatsKey::Retention(recipient.clone());
+        let (mut one_time, mut repeat): (u32, u32) =
+            env.storage().persistent().get(&retention_key).unwrap_or((0, 0));
+        match count {
+            0 => one_time += 1,
+            1 => {
+                one_time -= 1;
+                repeat += 1;
+            }
+            _ => return,
+        }
+        env.storage().persistent().set(&retention_key, &(one_time, repeat));
+    }
+
+    /// Undoes one tip from `supporter` in the recipient's retention counts
+    fn rollback_retention(env: &Env, recipient: &Address, supporter: &Address) {
+        let count_key = StatsKey::SupporterTips(recipient.clone(), supporter.clone());
+        let count: u32 = env.storage().persistent().get(&count_key).unwrap_or(0);
+        if count <= 1 {
+            env.storage().persistent().remove(&count_key);
+        } else {
+            env.storage().persistent().set(&count_key, &(count - 1));
+        }
+
+        let retention_key = StatsKey::Retention(recipient.clone());
+        let (mut one_time, mut repeat): (u32, u32) =
+            env.storage().persistent().get(&retention_key).unwrap_or((0, 0));
+        match count {
+            1 => one_time = one_time.saturating_sub(1),
+            2 => {
+                repeat = repeat.saturating_sub(1);
+                one_time += 1;
+            }
+            _ => return,
+        }
+        env.storage().persistent().set(&retention_key, &(one_time, repeat));
+    }
+
+    /// Adds one to a u32 counter stored in instance storage
+    fn increment_counter(env: &Env, key: &StatsKey) {
+        let count: u32 = env.storage().instance().get(key).unwrap_or(0);
+        let updated = count.checked_add(1).expect("arithmetic overflow");
+        env.storage().instance().set(key, &updated);
+    }
+
+    /// Stops counting a user towards a unique-user counter, if they were counted
+    fn uncount_unique(env: &Env, counted_key: &StatsKey, counter: &StatsKey) {
+        if !env.storage().persistent().has(counted_key) {
+            return;
+        }
+        env.storage().persistent().remove(counted_key);
+        let count: u32 = env.storage().instance().get(counter).unwrap_or(0);
+        let updated = count.checked_sub(1).expect("arithmetic overflow");
+        env.storage().instance().set(counter, &updated);
+    }
+
+    /// Updates the sender's user profile statistics
+    /// Internal function called by send_tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user sending the tip
+    /// * `amount` - Amount of the tip sent
+    fn update_sender_profile(env: &Env, user: &Address, amount: i128) {
+        // Construct the storage key for this user's profile
+        let profile_key = AccountKey::Profile(user.clone());
+
+        // Retrieve existing profile or create a new one
+        let mut profile: UserProfile = env
+            .storage()
+            .instance()
+            .get(&profile_key)
+            .unwrap_or_else(|| UserProfile {
+                tips_sent: 0,
+                tips_received: 0,
+                total_sent: 0,
+                total_received: 0,
+                first_interaction: env.ledger().timestamp(),
+                last_interaction: env.ledger().timestamp(),
+                streak: 0,
+                streak_updated: 0,
+            });
+
+        // Count the user as a new sender on their first-ever tip
+        let counted_key = StatsKey::CountedSender(user.clone());
+        if profile.tips_sent == 0 && !env.storage().persistent().has(&counted_key) {
+            Self::increment_counter(env, &StatsKey::UniqueSenders);
+            env.storage().persistent().set(&counted_key, &true);
+        }
+
+        // Increment send statistics
+        profile.tips_sent = profile.tips_sent.checked_add(1).expect("arithmetic overflow");
+        profile.total_sent = profile
+            .total_sent
+            .checked_add(amount)
+            .expect("arithmetic overflow");
+
+        // Extend the streak on the first tip of the next day; skipping a
+        // day starts it over
+        let now = env.ledger().timestamp();
+        let today = now / SECS_PER_DAY;
+        let last_day = profile.streak_updated / SECS_PER_DAY;
+        if profile.streak == 0 || today > last_day + 1 {
+            profile.streak = 1;
+        } else if today == last_day + 1 {
+            profile.streak = profile.streak.checked_add(1).expect("arithmetic overflow");
+        }
+        profile.streak_updated = now;
+
+        // Record the activity; first_interaction keeps its creation value
+        profile.last_interaction = now;
+
+        // Save the updated profile to storage
+        env.storage().instance().set(&profile_key, &profile);
+    }
+
+    /// Removes an unsent tip from the profiles and every statistic derived from it
+    /// A user whose only tip is unsent no longer counts as a unique sender or
+    /// recipient
+    fn rollback_profiles(env: &Env, tip: &Tip) {
+        if !tip.anonymous {
+            let sender_key = AccountKey::Profile(tip.from.clone());
+            let mut sender: UserProfile = env.storage().instance().get(&sender_key).unwrap();
+            sender.tips_sent = sender.tips_sent.checked_sub(1).expect("arithmetic overflow");
+            sender.total_sent = sender
+                .total_sent
+                .checked_sub(tip.amount)
+                .expect("arithmetic overflow");
+            env.storage().instance().set(&sender_key, &sender);
+            if sender.tips_sent == 0 {
+                Self::uncount_unique(
+                    env,
+                    &StatsKey::CountedSender(tip.from.clone()),
+                    &StatsKey::UniqueSenders,
+                );
+            }
+        }
+
+        let recipient_key = AccountKey::Profile(tip.to.clone());
+        let mut recipient: UserProfile = env.storage().instance().get(&recipient_key).unwrap();
+        recipient.tips_received = recipient
+            .tips_received
+            .checked_sub(1)
+            .expect("arithmetic overflow");
+        recipient.total_received = recipient
+            .total_received
+            .checked_sub(tip.amount)
+            .expect("arithmetic overflow");
+        env.storage().instance().set(&recipient_key, &recipient);
+        if recipient.tips_received == 0 {
+            Self::uncount_unique(
+                env,
+                &StatsKey::CountedRecipient(tip.to.clone()),
+                &StatsKey::UniqueRecipients,
+            );
+        }
+
+        Self::rollback_retention(env, &tip.to, &tip.from);
+        Self::unrecord_received(env, tip);
+        if tip.public {
+            Self::record_token_volume(env, &tip.token, -tip.amount);
+            Self::update_leaderboard(env, &tip.to, &tip.token, -tip.amount);
+        }
+        if tip.public && !tip.anonymous {
+            Self::record_pair_volume(env, &tip.from, &tip.to, -tip.amount);
+        }
+    }
+
+    /// Updates the recipient's user profile statistics
+    /// Internal function called by send_tip
+    /// 
+    /// # Arguments
+    /// * `env` - The Soroban environment
+    /// * `user` - Address of the user receiving the tip
+    /// * `amount` - Amount of the tip received
+    fn update_recipient_profile(env: &Env, user: &Address, amount: i128) {
+        // Construct the storage key for this user's profile
+        let profile_key = AccountKey::Profile(user.clone());
+
+        // Retrieve existing profile or create a new one
+        let mut profile: UserProfile = env
+            .storage()
+            .instance()
+            .get(&profile_key)
+            .unwrap_or_else(|| UserProfile {
+                tips_sent: 0,
+                tips_received: 0,
+                total_sent: 0,
+                total_received: 0,
+                first_interaction: env.ledger().timestamp(),
+                last_interaction: env.ledger().timestamp(),
+                streak: 0,
+                streak_updated: 0,
+            });
+
+        // Count the user as a new recipient on their first-ever tip received
+        let counted_key = StatsKey::CountedRecipient(user.clone());
+        if profile.tips_received == 0 && !env.storage().persistent().has(&counted_key) {
+            Self::increment_counter(env, &StatsKey::UniqueRecipients);
+            env.storage().persistent().set(&counted_key, &true);
+        }
+
+        // Increment receive statistics
+        profile.tips_received = profile
+            .tips_received
+            .checked_add(1)
+            .expect("arithmetic overflow");
+        profile.total_received = profile
+            .total_received
+            .checked_add(amount)
+            .expect("arithmetic overflow");
+
+        // Record the activity; first_interaction keeps its creation value
+        profile.last_interaction = env.ledger().timestamp();
+
+        // Save the updated profile to storage
+        env.storage().instance().set(&profile_key, &profile);
+    }
+}
+
+// ============================================================================
+// TESTS MODULE
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Events, Ledger};
+    use soroban_sdk::token::StellarAssetClient;
+    use soroban_sdk::{symbol_short, IntoVal};
+
+    /// Registers and initializes the microtip contract
+    /// Returns the contract address, its client, and the admin address
+    fn setup_contract(env: &Env) -> (Address, MicrotipContractClient<'_>, Address) {
+        let contract_id = env.register_contract(None, MicrotipContract);
+        let client = MicrotipContractClient::new(env, &contract_id);
+        let admin = Address::generate(env);
+        client.init(&admin);
+        (contract_id, client, admin)
+    }
+
+    /// Token stand-in whose transfer calls back into the tip contract
+    mod reentrant_token {
+        use super::*;
+
+        #[contract]
+        pub struct ReentrantToken;
+
+        #[contractimpl]
+        impl ReentrantToken {
+            pub fn set_target(env: Env, target: Address, user: Address) {
+                env.storage().instance().set(&symbol_short!("target"), &target);
+                env.storage().instance().set(&symbol_short!("user"), &user);
+            }
+
+            pub fn mint(env: Env, to: Address, amount: i128) {
+                let balance = Self::balance(env.clone(), to.clone());
+                env.storage().instance().set(&to, &(balance + amount));
+            }
+
+            /// Attempts a nested withdraw, records whether it was rejected, then
+            /// moves the funds like a normal token
+            pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+                let target: Address = env.storage().instance().get(&symbol_short!("target")).unwrap();
+                let user: Address = env.storage().instance().get(&symbol_short!("user")).unwrap();
+                let nested = MicrotipContractClient::new(&env, &target).try_withdraw(
+                    &user,
+                    &env.current_contract_address(),
+                    &amount,
+                );
+                env.storage().instance().set(&symbol_short!("rejected"), &nested.is_err());
+
+                let from_balance = Self::balance(env.clone(), from.clone());
+                env.storage().instance().set(&from, &(from_balance - amount));
+                let to_balance = Self::balance(env.clone(), to.clone());
+                env.storage().instance().set(&to, &(to_balance + amount));
+            }
+
+            pub fn balance(env: Env, id: Address) -> i128 {
+                env.storage().instance().get(&id).unwrap_or(0)
+            }
+
+            pub fn rejected(env: Env) -> bool {
+                env.storage().instance().get(&symbol_short!("rejected")).unwrap_or(false)
+            }
+        }
+    }
+    use reentrant_token::{ReentrantToken, ReentrantTokenClient};
+
+    /// Token stand-in whose transfer only moves half the requested amount
+    mod short_token {
+        use super::*;
+
+        #[contract]
+        pub struct ShortToken;
+
+        #[contractimpl]
+        impl ShortToken {
+            pub fn mint(env: Env, to: Address, amount: i128) {
+                let balance = Self::balance(env.clone(), to.clone());
+                env.storage().instance().set(&to, &(balance + amount));
+            }
+
+            pub fn balance(env: Env, id: Address) -> i128 {
+                env.storage().instance().get(&id).unwrap_or(0)
+            }
+
+            pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+                let moved = amount / 2;
+                let from_balance = Self::balance(env.clone(), from.clone());
+                env.storage().instance().set(&from, &(from_balance - moved));
+                Self::mint(env, to, moved);
+            }
+        }
+    }
+    use short_token::{ShortToken, ShortTokenClient};
+
+    /// Deploys a Stellar asset contract to use as the tipped token
+    fn create_token<'a>(env: &Env) -> (Address, TokenClient<'a>, StellarAssetClient<'a>) {
+        let issuer = Address::generate(env);
+        let sac = env.register_stellar_asset_contract_v2(issuer);
+        let address = sac.address();
+        (
+            address.clone(),
+            TokenClient::new(env, &address),
+            StellarAssetClient::new(env, &address),
+        )
+    }
+
+    #[test]
+    fn test_send_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        env.ledger().set_timestamp(1_000);
+
+        let tip_id =
+            client.send_tip(&sender, &recipient, &token, &300, &String::from_str(&env, "great work"));
+
+        // The published event is the tip notification for this tip
+        let (event_contract, topics, data) = env.events().all().last().unwrap();
+        assert_eq!(event_contract, contract_id);
+        assert_eq!(
+            topics,
+            (symbol_short!("tip"), token.clone(), recipient.clone()).into_val(&env)
+        );
+        let payload: (u64, Address, Address, i128, u64) = data.into_val(&env);
+        assert_eq!(payload, (1, sender.clone(), recipient.clone(), 300, 1_000));
+
+        // Funds moved into the contract
+        assert_eq!(token_client.balance(&sender), 700);
+        assert_eq!(token_client.balance(&contract_id), 300);
+
+        // The tip is recorded
+        let tip = client.get_tip(&tip_id);
+        assert_eq!(tip.from, sender);
+        assert_eq!(tip.to, recipient);
+        assert_eq!(tip.amount, 300);
+        assert_eq!(tip.timestamp, 1_000);
+        assert_eq!(client.get_total_tips_count(), 1);
+
+        // The recipient's balance is credited
+        let balance = client.get_balance(&recipient, &token);
+        assert_eq!(balance.total_received, 300);
+        assert_eq!(balance.available, 300);
+        assert_eq!(balance.withdrawn, 0);
+
+        // Both profiles are updated
+        let sender_profile = client.get_user_profile(&sender);
+        assert_eq!(sender_profile.tips_sent, 1);
+        assert_eq!(sender_profile.total_sent, 300);
+        let recipient_profile = client.get_user_profile(&recipient);
+        assert_eq!(recipient_profile.tips_received, 1);
+        assert_eq!(recipient_profile.total_received, 300);
+    }
+
+    #[test]
+    fn test_withdraw() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        client.send_tip(&sender, &recipient, &token, &500, &String::from_str(&env, ""));
+        client.withdraw(&recipient, &token, &200);
+
+        let balance = client.get_balance(&recipient, &token);
+        assert_eq!(balance.total_received, 500);
+        assert_eq!(balance.available, 300);
+        assert_eq!(balance.withdrawn, 200);
+        assert_eq!(token_client.balance(&recipient), 200);
+        assert_eq!(token_client.balance(&contract_id), 300);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient available balance for withdrawal")]
+    fn test_withdraw_more_than_available_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        client.send_tip(&sender, &recipient, &token, &100, &String::from_str(&env, ""));
+        client.withdraw(&recipient, &token, &101);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot send a tip to yourself")]
+    fn test_self_tip_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        client.send_tip(&sender, &sender, &token, &100, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    #[should_panic(expected = "Tip amount must be greater than zero")]
+    fn test_zero_amount_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        client.send_tip(&sender, &recipient, &token, &0, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    fn test_get_contract_token_balance() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+
+        // Fund the contract directly, outside of any tip
+        token_admin.mint(&contract_id, &1_000);
+
+        assert_eq!(client.get_contract_token_balance(&token), 1_000);
+        assert_eq!(client.reconcile(&token), (1_000, 0));
+    }
+
+    #[test]
+    fn test_get_solvency() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        // Tips in and a partial withdrawal out keep the books balanced
+        client.send_tip(&sender, &recipient, &token, &600, &String::from_str(&env, "gm"));
+        client.withdraw(&recipient, &token, &200);
+        assert_eq!(client.get_solvency(&token), (400, 400));
+
+        // Move funds out behind the contract's back to induce a shortfall
+        let outsider = Address::generate(&env);
+        token_client.transfer(&contract_id, &outsider, &50);
+        let (held, owed) = client.get_solvency(&token);
+        assert_eq!((held, owed), (350, 400));
+        assert!(held < owed);
+    }
+
+    #[test]
+    #[should_panic(expected = "arithmetic overflow")]
+    fn test_balance_overflow_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        token_admin.mint(&sender, &10);
+
+        // Seed a recipient balance sitting just below the i128 ceiling
+        env.as_contract(&contract_id, || {
+            env.storage().instance().set(
+                &AccountKey::Balance(recipient.clone(), token.clone()),
+                &Balance {
+                    total_received: i128::MAX - 5,
+                    available: i128::MAX - 5,
+                    withdrawn: 0,
+                    token: token.clone(),
+                },
+            );
+        });
+
+        // Crediting another 10 must panic instead of wrapping
+        client.send_tip(&sender, &recipient, &token, &10, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    fn test_get_tips_between() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        let msg = String::from_str(&env, "thanks");
+        let first = client.send_tip(&sender, &alice, &token, &100, &msg);
+        client.send_tip(&sender, &bob, &token, &200, &msg);
+        let third = client.send_tip(&sender, &alice, &token, &300, &msg);
+
+        // Only the tips to alice are returned, in order
+        let tips = client.get_tips_between(&sender, &alice, &0, &10);
+        assert_eq!(tips.len(), 2);
+        assert_eq!(tips.get(0).unwrap().amount, 100);
+        assert_eq!(tips.get(1).unwrap().amount, 300);
+        assert_eq!(client.get_tip(&first).amount, 100);
+        assert_eq!(client.get_tip(&third).amount, 300);
+
+        // Pagination skips matches, not raw index entries
+        let page = client.get_tips_between(&sender, &alice, &1, &10);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page.get(0).unwrap().amount, 300);
+
+        let to_bob = client.get_tips_between(&sender, &bob, &0, &10);
+        assert_eq!(to_bob.len(), 1);
+        assert_eq!(to_bob.get(0).unwrap().to, bob);
+    }
+
+    #[test]
+    fn test_token_withdraw_allowlist() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (restricted, restricted_client, restricted_admin) = create_token(&env);
+        let (open, open_client, open_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let vault = Address::generate(&env);
+        let elsewhere = Address::generate(&env);
+        restricted_admin.mint(&sender, &1_000);
+        open_admin.mint(&sender, &1_000);
+
+        let msg = String::from_str(&env, "");
+        client.send_tip(&sender, &creator, &restricted, &500, &msg);
+        client.send_tip(&sender, &creator, &open, &500, &msg);
+
+        let mut destinations = Vec::new(&env);
+        destinations.push_back(vault.clone());
+        client.set_token_withdraw_allowlist(&admin, &restricted, &destinations, &symbol_short!("none"));
+
+        // An allowlisted destination succeeds
+        client.withdraw_to(&creator, &restricted, &100, &vault);
+        assert_eq!(restricted_client.balance(&vault), 100);
+
+        // Any other destination is rejected for the restricted token
+        let result = client.try_withdraw_to(&creator, &restricted, &100, &elsewhere);
+        assert!(result.is_err());
+        assert_eq!(restricted_client.balance(&elsewhere), 0);
+
+        // Tokens without an allowlist remain unrestricted
+        client.withdraw_to(&creator, &open, &100, &elsewhere);
+        assert_eq!(open_client.balance(&elsewhere), 100);
+    }
+
+    #[test]
+    fn test_get_tips_grouped_by_sender() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&alice, &1_000);
+        token_admin.mint(&bob, &1_000);
+
+        let msg = String::from_str(&env, "");
+        let a1 = client.send_tip(&alice, &creator, &token, &10, &msg);
+        let b1 = client.send_tip(&bob, &creator, &token, &20, &msg);
+        let a2 = client.send_tip(&alice, &creator, &token, &30, &msg);
+
+        let groups = client.get_tips_grouped_by_sender(&creator, &0, &10);
+        assert_eq!(groups.len(), 2);
+
+        let (first_sender, first_ids) = groups.get(0).unwrap();
+        assert_eq!(first_sender, alice);
+        assert_eq!(first_ids, Vec::from_array(&env, [a1, a2]));
+
+        let (second_sender, second_ids) = groups.get(1).unwrap();
+        assert_eq!(second_sender, bob);
+        assert_eq!(second_ids, Vec::from_array(&env, [b1]));
+
+        // Pages group only the tips they cover
+        let page = client.get_tips_grouped_by_sender(&creator, &1, &10);
+        assert_eq!(page.get(0).unwrap(), (bob, Vec::from_array(&env, [b1])));
+        assert_eq!(page.get(1).unwrap(), (alice, Vec::from_array(&env, [a2])));
+        assert_eq!(client.get_tips_grouped_by_sender(&creator, &5, &10).len(), 0);
+    }
+
+    #[test]
+    fn test_anonymous_tip_masks_sender() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        let tip_id =
+            client.send_tip_anonymous(&sender, &creator, &token, &250, &String::from_str(&env, "shh"));
+
+        // Public queries report the sentinel instead of the real sender
+        let sentinel = Address::from_string(&String::from_str(&env, ANONYMOUS_SENDER));
+        let tips = client.get_tips_for_user(&creator);
+        assert_eq!(tips.len(), 1);
+        let public = tips.get(0).unwrap();
+        assert!(public.anonymous);
+        assert_eq!(public.from, sentinel);
+        assert_eq!(client.get_tip(&tip_id).from, sentinel);
+
+        // The refund still reaches the real sender
+        client.refund_tip(&creator, &tip_id);
+        assert_eq!(token_client.balance(&sender), 1_000);
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+    }
+
+    #[test]
+    fn test_token_allowlist() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (approved, _, approved_admin) = create_token(&env);
+        let (spam, _, spam_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        approved_admin.mint(&sender, &1_000);
+        spam_admin.mint(&sender, &1_000);
+        let msg = String::from_str(&env, "");
+
+        // With an empty allowlist every token is accepted
+        assert!(client.is_token_allowed(&spam));
+        client.send_tip(&sender, &creator, &spam, &10, &msg);
+
+        // Once a token is allowed, unlisted tokens are rejected
+        client.allow_token(&admin, &approved, &symbol_short!("none"));
+        assert!(client.is_token_allowed(&approved));
+        assert!(!client.is_token_allowed(&spam));
+        client.send_tip(&sender, &creator, &approved, &10, &msg);
+        assert!(client.try_send_tip(&sender, &creator, &spam, &10, &msg).is_err());
+
+        // Removing the last allowed token returns to allow-all
+        client.disallow_token(&admin, &approved, &symbol_short!("none"));
+        assert!(client.is_token_allowed(&spam));
+        client.send_tip(&sender, &creator, &spam, &10, &msg);
+    }
+
+    #[test]
+    fn test_fee_in_separate_token() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let (fee_token, fee_client, fee_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        fee_admin.mint(&sender, &100);
+
+        client.set_fee_config(&admin, &fee_token, &5, &symbol_short!("none"));
+        client.send_tip(&sender, &creator, &token, &400, &String::from_str(&env, ""));
+
+        // The fee is taken in the fee token only
+        assert_eq!(fee_client.balance(&sender), 95);
+        assert_eq!(client.reconcile(&fee_token), (5, 5));
+
+        // The recipient is credited the full tip
+        assert_eq!(token_client.balance(&sender), 600);
+        assert_eq!(client.get_balance(&creator, &token).available, 400);
+        assert_eq!(client.reconcile(&token), (400, 0));
+    }
+
+    #[test]
+    fn test_freeze_user() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let msg = String::from_str(&env, "");
+
+        client.send_tip(&sender, &creator, &token, &100, &msg);
+        client.freeze_user(&admin, &creator, &Symbol::new(&env, "dispute"));
+        assert!(client.is_frozen(&creator));
+
+        // A frozen user cannot withdraw
+        assert!(client.try_withdraw(&creator, &token, &50).is_err());
+
+        // But can still receive tips
+        client.send_tip(&sender, &creator, &token, &100, &msg);
+        assert_eq!(client.get_balance(&creator, &token).available, 200);
+
+        // And can withdraw again once unfrozen
+        client.unfreeze_user(&admin, &creator, &Symbol::new(&env, "resolved"));
+        assert!(!client.is_frozen(&creator));
+        client.withdraw(&creator, &token, &200);
+        assert_eq!(token_client.balance(&creator), 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "Account frozen")]
+    fn test_frozen_withdraw_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &100);
+
+        client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, ""));
+        client.freeze_user(&admin, &creator, &Symbol::new(&env, "dispute"));
+        client.withdraw(&creator, &token, &100);
+    }
+
+    #[test]
+    fn test_profile_interaction_timestamps() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let msg = String::from_str(&env, "");
+
+        env.ledger().set_timestamp(1_000);
+        client.send_tip(&sender, &recipient, &token, &10, &msg);
+
+        env.ledger().set_timestamp(5_000);
+        client.send_tip(&sender, &recipient, &token, &10, &msg);
+
+        // first_interaction keeps the first tip's time, last_interaction moves on
+        let sender_profile = client.get_user_profile(&sender);
+        assert_eq!(sender_profile.first_interaction, 1_000);
+        assert_eq!(sender_profile.last_interaction, 5_000);
+        let recipient_profile = client.get_user_profile(&recipient);
+        assert_eq!(recipient_profile.first_interaction, 1_000);
+        assert_eq!(recipient_profile.last_interaction, 5_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Caller is not the admin")]
+    fn test_upgrade_requires_admin() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let outsider = Address::generate(&env);
+
+        client.upgrade(&outsider, &BytesN::from_array(&env, &[0; 32]), &symbol_short!("none"));
+    }
+
+    #[test]
+    fn test_send_split_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        let recipients = Vec::from_array(&env, [alice.clone(), bob.clone()]);
+        let shares = Vec::from_array(&env, [70u32, 30u32]);
+        let ids = client.send_split_tip(
+            &sender,
+            &token,
+            &1_000,
+            &recipients,
+            &shares,
+            &String::from_str(&env, "collab"),
+        );
+
+        assert_eq!(ids.len(), 2);
+        assert_eq!(client.get_balance(&alice, &token).available, 700);
+        assert_eq!(client.get_balance(&bob, &token).available, 300);
+        assert_eq!(token_client.balance(&contract_id), 1_000);
+    }
+
+    #[test]
+    fn test_split_tip_remainder_goes_to_first() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let a = Address::generate(&env);
+        let b = Address::generate(&env);
+        let c = Address::generate(&env);
+        token_admin.mint(&sender, &100);
+
+        // 100 split three ways leaves a remainder of 1
+        let recipients = Vec::from_array(&env, [a.clone(), b.clone(), c.clone()]);
+        let shares = Vec::from_array(&env, [1u32, 1u32, 1u32]);
+        client.send_split_tip(&sender, &token, &100, &recipients, &shares, &String::from_str(&env, ""));
+
+        let total = client.get_balance(&a, &token).available
+            + client.get_balance(&b, &token).available
+            + client.get_balance(&c, &token).available;
+        assert_eq!(total, 100);
+        assert_eq!(client.get_balance(&a, &token).available, 34);
+        assert_eq!(token_client.balance(&contract_id), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Shares must not sum to zero")]
+    fn test_split_tip_zero_shares_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        token_admin.mint(&sender, &100);
+
+        let recipients = Vec::from_array(&env, [Address::generate(&env), Address::generate(&env)]);
+        let shares = Vec::from_array(&env, [0u32, 0u32]);
+        client.send_split_tip(&sender, &token, &100, &recipients, &shares, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    #[should_panic(expected = "Recipients and shares must have the same length")]
+    fn test_split_tip_length_mismatch_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        token_admin.mint(&sender, &100);
+
+        let recipients = Vec::from_array(&env, [Address::generate(&env)]);
+        let shares = Vec::from_array(&env, [1u32, 1u32]);
+        client.send_split_tip(&sender, &token, &100, &recipients, &shares, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    fn test_refund_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        // 5% reversal fee
+        client.set_refund_fee_bps(&admin, &500, &symbol_short!("none"));
+        let tip_id = client.send_tip(&sender, &creator, &token, &1_000, &String::from_str(&env, ""));
+        client.refund_tip(&creator, &tip_id);
+
+        // The sender gets the net refund and the platform keeps the fee
+        assert_eq!(token_client.balance(&sender), 950);
+        assert_eq!(client.reconcile(&token), (50, 50));
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+    }
+
+    #[test]
+    fn test_zero_refund_fee_is_noop() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        assert_eq!(client.get_refund_fee_bps(), 0);
+        let tip_id = client.send_tip(&sender, &creator, &token, &1_000, &String::from_str(&env, ""));
+        client.refund_tip(&creator, &tip_id);
+
+        assert_eq!(token_client.balance(&sender), 1_000);
+        assert_eq!(client.reconcile(&token), (0, 0));
+    }
+
+    #[test]
+    fn test_get_recent_tips() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        let msg = String::from_str(&env, "");
+        for amount in 1..=5 {
+            client.send_tip(&sender, &creator, &token, &(amount as i128), &msg);
+        }
+
+        // The last three, newest first
+        let recent = client.get_recent_tips(&3);
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent.get(0).unwrap().amount, 5);
+        assert_eq!(recent.get(1).unwrap().amount, 4);
+        assert_eq!(recent.get(2).unwrap().amount, 3);
+
+        // Asking for more than exist returns what there is
+        assert_eq!(client.get_recent_tips(&20).len(), 5);
+    }
+
+    #[test]
+    fn test_send_tip_sponsored() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let (fee_token, fee_client, fee_admin) = create_token(&env);
+        let sponsor = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        fee_admin.mint(&sponsor, &100);
+        client.set_fee_config(&admin, &fee_token, &10, &symbol_short!("none"));
+
+        let tip_id = client.send_tip_sponsored(
+            &sponsor,
+            &sender,
+            &creator,
+            &token,
+            &300,
+            &String::from_str(&env, ""),
+        );
+
+        // Both the sponsor and the sender had to authorize
+        let auths = env.auths();
+        assert!(auths.iter().any(|(addr, _)| *addr == sponsor));
+        assert!(auths.iter().any(|(addr, _)| *addr == sender));
+
+        // The tip comes from the sender, the fee from the sponsor
+        assert_eq!(token_client.balance(&sender), 700);
+        assert_eq!(fee_client.balance(&sponsor), 90);
+        assert_eq!(client.get_balance(&creator, &token).available, 300);
+        assert_eq!(client.get_tip(&tip_id).sponsor, Some(sponsor));
+    }
+
+    #[test]
+    fn test_get_overdue_acknowledgements() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let msg = String::from_str(&env, "");
+
+        env.ledger().set_timestamp(1_000);
+        let old = client.send_tip(&sender, &creator, &token, &10, &msg);
+        env.ledger().set_timestamp(9_000);
+        client.send_tip(&sender, &creator, &token, &20, &msg);
+
+        // At t=10_000 only the first tip is more than an hour old
+        env.ledger().set_timestamp(10_000);
+        let overdue = client.get_overdue_acknowledgements(&creator, &3_600, &0, &10);
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue.get(0).unwrap().amount, client.get_tip(&old).amount);
+
+        // Later, both are overdue and can be paged through
+        env.ledger().set_timestamp(20_000);
+        let page = client.get_overdue_acknowledgements(&creator, &3_600, &1, &10);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page.get(0).unwrap().amount, 20);
+        assert_eq!(client.get_overdue_acknowledgements(&creator, &3_600, &0, &1).len(), 1);
+    }
+
+    #[test]
+    fn test_max_message_len() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        assert_eq!(client.get_max_message_len(), 256);
+        client.set_max_message_len(&admin, &5, &symbol_short!("none"));
+        assert_eq!(client.get_max_message_len(), 5);
+
+        // Exactly at the limit is accepted
+        client.send_tip(&sender, &creator, &token, &10, &String::from_str(&env, "hello"));
+
+        // One byte over is rejected
+        let long = String::from_str(&env, "hello!");
+        assert!(client.try_send_tip(&sender, &creator, &token, &10, &long).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Message length exceeds ceiling")]
+    fn test_max_message_len_ceiling() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+
+        client.set_max_message_len(&admin, &1_025, &symbol_short!("none"));
+    }
+
+    #[test]
+    fn test_acknowledge_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        env.ledger().set_timestamp(1_000);
+        let tip_id = client.send_tip(&sender, &creator, &token, &10, &String::from_str(&env, ""));
+        assert_eq!(client.get_ack(&tip_id), None);
+
+        let thanks = Symbol::new(&env, "thanks");
+        client.acknowledge_tip(&creator, &tip_id, &thanks);
+        assert_eq!(client.get_ack(&tip_id), Some(thanks));
+
+        // Acknowledged tips are no longer overdue
+        env.ledger().set_timestamp(100_000);
+        assert_eq!(client.get_overdue_acknowledgements(&creator, &0, &0, &10).len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the recipient can acknowledge a tip")]
+    fn test_acknowledge_tip_by_non_recipient_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        let tip_id = client.send_tip(&sender, &creator, &token, &10, &String::from_str(&env, ""));
+        client.acknowledge_tip(&sender, &tip_id, &Symbol::new(&env, "thanks"));
+    }
+
+    #[test]
+    fn test_get_retention() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let loyal = Address::generate(&env);
+        let casual = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&loyal, &1_000);
+        token_admin.mint(&casual, &1_000);
+        let msg = String::from_str(&env, "");
+
+        client.send_tip(&loyal, &creator, &token, &10, &msg);
+        client.send_tip(&casual, &creator, &token, &10, &msg);
+        client.send_tip(&loyal, &creator, &token, &10, &msg);
+
+        assert_eq!(client.get_retention(&creator), (1, 1));
+    }
+
+    #[test]
+    fn test_withdraw_rejects_reentrant_token() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let user = Address::generate(&env);
+        let token = env.register_contract(None, ReentrantToken);
+        let token_client = ReentrantTokenClient::new(&env, &token);
+        token_client.set_target(&contract_id, &user);
+        token_client.mint(&contract_id, &100);
+
+        // Give the user a balance in the malicious token
+        env.as_contract(&contract_id, || {
+            MicrotipContract::update_balance(&env, &user, &token, 100, true);
+        });
+
+        // The token's transfer re-enters withdraw; only the outer call pays out
+        client.withdraw(&user, &token, &50);
+        assert!(token_client.rejected());
+        assert_eq!(client.get_balance(&user, &token).available, 50);
+        assert_eq!(token_client.balance(&user), 50);
+    }
+
+    #[test]
+    #[should_panic(expected = "reentrant call")]
+    fn test_withdraw_rejects_held_lock() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, ""));
+
+        // A call still holding the guard blocks any other guarded entrypoint
+        env.as_contract(&contract_id, || {
+            env.storage().instance().set(&ConfigKey::Lock, &true);
+        });
+        client.withdraw(&creator, &token, &50);
+    }
+
+    #[test]
+    fn test_get_all_balances_for_user() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (usdc, _, usdc_admin) = create_token(&env);
+        let (xlm, _, xlm_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        usdc_admin.mint(&sender, &1_000);
+        xlm_admin.mint(&sender, &1_000);
+        let msg = String::from_str(&env, "");
+
+        client.send_tip(&sender, &creator, &usdc, &100, &msg);
+        client.send_tip(&sender, &creator, &xlm, &200, &msg);
+        client.send_tip(&sender, &creator, &usdc, &50, &msg);
+
+        // One entry per token, without duplicates
+        let balances = client.get_all_balances_for_user(&creator);
+        assert_eq!(balances.len(), 2);
+        let first = balances.get(0).unwrap();
+        assert_eq!(first.token, usdc);
+        assert_eq!(first.available, 150);
+        let second = balances.get(1).unwrap();
+        assert_eq!(second.token, xlm);
+        assert_eq!(second.available, 200);
+    }
+
+    #[test]
+    fn test_get_limits() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+
+        let limits = client.get_limits();
+        assert_eq!(limits.min_tip_amount, 1);
+        assert_eq!(limits.max_message_len, 256);
+        assert_eq!(limits.min_withdrawal_amount, 1);
+
+        client.set_max_message_len(&admin, &512, &symbol_short!("none"));
+        client.set_max_message_len(&admin, &128, &symbol_short!("none"));
+        assert_eq!(client.get_limits().max_message_len, 128);
+    }
+
+    #[test]
+    fn test_message_min_amount() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let hello = String::from_str(&env, "hello");
+        let empty = String::from_str(&env, "");
+
+        // No restriction by default
+        client.send_tip(&sender, &creator, &token, &1, &hello);
+
+        client.set_message_min_amount(&admin, &100, &symbol_short!("none"));
+        assert_eq!(client.get_limits().message_min_amount, 100);
+
+        // A small tip can still be sent without a message
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+
+        // But not with one
+        assert!(client.try_send_tip(&sender, &creator, &token, &10, &hello).is_err());
+
+        // Tips at the threshold may carry a message
+        client.send_tip(&sender, &creator, &token, &100, &hello);
+    }
+
+    #[test]
+    fn test_reclaim_expired_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_claim_window(&admin, &86_400, &symbol_short!("none"));
+
+        env.ledger().set_timestamp(1_000);
+        let tip_id = client.send_tip(&sender, &creator, &token, &400, &String::from_str(&env, ""));
+
+        // Too early: the recipient still has time to claim
+        env.ledger().set_timestamp(1_000 + 86_399);
+        assert!(client.try_reclaim_expired_tip(&sender, &tip_id).is_err());
+
+        // After the window the sender gets the tip back
+        env.ledger().set_timestamp(1_000 + 86_400);
+        client.reclaim_expired_tip(&sender, &tip_id);
+        assert_eq!(token_client.balance(&sender), 1_000);
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+
+        // And cannot reclaim it twice
+        assert!(client.try_reclaim_expired_tip(&sender, &tip_id).is_err());
+    }
+
+    #[test]
+    fn test_reclaim_blocked_while_paused() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_claim_window(&admin, &100, &symbol_short!("none"));
+
+        let tip_id = client.send_tip(&sender, &creator, &token, &400, &String::from_str(&env, ""));
+        env.ledger().set_timestamp(env.ledger().timestamp() + 100);
+
+        client.pause(&admin, &symbol_short!("none"));
+        assert!(client.try_reclaim_expired_tip(&sender, &tip_id).is_err());
+
+        client.unpause(&admin, &symbol_short!("none"));
+        client.reclaim_expired_tip(&sender, &tip_id);
+        assert_eq!(token_client.balance(&sender), 1_000);
+    }
+
+    #[test]
+    fn test_reclaim_event_masks_anonymous_sender() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_claim_window(&admin, &100, &symbol_short!("none"));
+
+        let tip_id =
+            client.send_tip_anonymous(&sender, &creator, &token, &400, &String::from_str(&env, ""));
+        env.ledger().set_timestamp(env.ledger().timestamp() + 100);
+        client.reclaim_expired_tip(&sender, &tip_id);
+        assert_eq!(token_client.balance(&sender), 1_000);
+
+        let (_, topics, data) = env.events().all().last().unwrap();
+        let name: Symbol = topics.get_unchecked(0).into_val(&env);
+        assert_eq!(name, Symbol::new(&env, "tip_reclaimed"));
+        let payload: (u64, u64, Address, i128, u64) = data.into_val(&env);
+        assert_eq!(payload.2, Address::from_string(&String::from_str(&env, ANONYMOUS_SENDER)));
+        assert_eq!(payload.3, 400);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tip already claimed")]
+    fn test_reclaim_after_withdrawal_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_claim_window(&admin, &100, &symbol_short!("none"));
+
+        let tip_id = client.send_tip(&sender, &creator, &token, &400, &String::from_str(&env, ""));
+        client.withdraw(&creator, &token, &1);
+
+        env.ledger().set_timestamp(env.ledger().timestamp() + 100);
+        client.reclaim_expired_tip(&sender, &tip_id);
+    }
+
+    #[test]
+    fn test_get_withdrawals_paged() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let vault = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.send_tip(&sender, &creator, &token, &1_000, &String::from_str(&env, ""));
+
+        client.withdraw(&creator, &token, &10);
+        client.withdraw(&creator, &token, &20);
+        client.withdraw_to(&creator, &token, &30, &vault);
+
+        let page = client.get_withdrawals_paged(&None, &2);
+        assert_eq!(page.withdrawals.len(), 2);
+        assert_eq!(page.withdrawals.get(0).unwrap().amount, 10);
+        assert_eq!(page.withdrawals.get(1).unwrap().amount, 20);
+        assert_eq!(page.next, Some(2));
+
+        // The last page carries no cursor
+        let page = client.get_withdrawals_paged(&page.next, &2);
+        assert_eq!(page.withdrawals.len(), 1);
+        let last = page.withdrawals.get(0).unwrap();
+        assert_eq!(last.amount, 30);
+        assert_eq!(last.destination, vault);
+        assert_eq!(page.next, None);
+
+        // A cursor at the largest id yields an empty page rather than overflowing
+        let page = client.get_withdrawals_paged(&Some(u64::MAX), &2);
+        assert_eq!(page.withdrawals.len(), 0);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn test_get_platform_stats() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&alice, &1_000);
+        token_admin.mint(&bob, &1_000);
+        client.allow_token(&admin, &token, &symbol_short!("none"));
+        let msg = String::from_str(&env, "");
+
+        // Alice tips repeatedly, so she must only be counted once
+        client.send_tip(&alice, &creator, &token, &10, &msg);
+        client.send_tip(&alice, &creator, &token, &10, &msg);
+        client.send_tip(&alice, &bob, &token, &10, &msg);
+        client.send_tip(&bob, &creator, &token, &10, &msg);
+
+        let stats = client.get_platform_stats();
+        assert_eq!(stats.total_tips, 4);
+        assert_eq!(stats.unique_senders, 2);
+        assert_eq!(stats.unique_recipients, 2);
+        assert_eq!(stats.supported_tokens, 1);
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, _) = create_token(&env);
+        let user = Address::generate(&env);
+
+        env.ledger().set_timestamp(100);
+        client.freeze_user(&admin, &user, &Symbol::new(&env, "legal_hold"));
+        env.ledger().set_timestamp(200);
+        client.allow_token(&admin, &token, &symbol_short!("listing"));
+
+        let log = client.get_audit_log(&10);
+        assert_eq!(log.len(), 2);
+
+        let freeze = log.get(0).unwrap();
+        assert_eq!(freeze.actor, admin);
+        assert_eq!(freeze.action, Symbol::new(&env, "freeze"));
+        assert_eq!(freeze.target, user);
+        assert_eq!(freeze.reason, Symbol::new(&env, "legal_hold"));
+        assert_eq!(freeze.timestamp, 100);
+
+        let allow = log.get(1).unwrap();
+        assert_eq!(allow.action, Symbol::new(&env, "allow_token"));
+        assert_eq!(allow.target, token);
+        assert_eq!(allow.reason, symbol_short!("listing"));
+        assert_eq!(allow.timestamp, 200);
+
+        // A smaller limit keeps the most recent entries
+        let latest = client.get_audit_log(&1);
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest.get(0).unwrap().action, Symbol::new(&env, "allow_token"));
+    }
+
+    #[test]
+    fn test_get_balance_with_decimals() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.send_tip(&sender, &creator, &token, &250, &String::from_str(&env, ""));
+
+        let (balance, decimals) = client.get_balance_with_decimals(&creator, &token);
+        assert_eq!(balance.available, 250);
+        assert_eq!(decimals, 7);
+
+        // A contract without `decimals` falls back to the Stellar default
+        let no_decimals = env.register_contract(None, ReentrantToken);
+        let (_, fallback) = client.get_balance_with_decimals(&creator, &no_decimals);
+        assert_eq!(fallback, 7);
+    }
+
+    #[test]
+    fn test_emergency_withdraw() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let rescue = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.send_tip(&sender, &creator, &token, &600, &String::from_str(&env, ""));
+
+        // Refused while the platform is running
+        assert!(client.try_emergency_withdraw(&admin, &token, &rescue, &symbol_short!("none")).is_err());
+
+        // Pausing halts normal activity and unlocks the drain
+        client.pause(&admin, &symbol_short!("none"));
+        assert!(client.is_paused());
+        assert!(client.try_withdraw(&creator, &token, &100).is_err());
+
+        // Only the surplus beyond the creator's balance is drained
+        token_admin.mint(&contract_id, &50);
+        assert_eq!(client.emergency_withdraw(&admin, &token, &rescue, &symbol_short!("none")), 50);
+        assert_eq!(token_client.balance(&rescue), 50);
+        assert_eq!(token_client.balance(&contract_id), 600);
+
+        let (_, topics, _) = env.events().all().last().unwrap();
+        assert_eq!(
+            topics,
+            (Symbol::new(&env, "emergency_drain"), token.clone()).into_val(&env)
+        );
+    }
+
+    #[test]
+    fn test_handles() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let handle = String::from_str(&env, "alice");
+
+        client.set_handle(&alice, &handle);
+        assert_eq!(client.resolve_handle(&handle), alice);
+        assert_eq!(client.get_handle(&alice), Some(handle.clone()));
+        assert_eq!(client.get_handle(&bob), None);
+
+        // Another user cannot take it, and overlong handles are rejected
+        assert!(client.try_set_handle(&bob, &handle).is_err());
+        let long = String::from_str(&env, "abcdefghijklmnopqrstuvwxyz0123456");
+        assert!(client.try_set_handle(&bob, &long).is_err());
+
+        // Renaming frees the old handle for others
+        let renamed = String::from_str(&env, "alice2");
+        client.set_handle(&alice, &renamed);
+        assert_eq!(client.resolve_handle(&renamed), alice);
+        client.set_handle(&bob, &handle);
+        assert_eq!(client.resolve_handle(&handle), bob);
+    }
+
+    #[test]
+    fn test_default_message() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        let default = String::from_str(&env, "Thanks for your work!");
+        client.set_default_message(&admin, &default, &symbol_short!("none"));
+        assert_eq!(client.get_default_message(), default);
+
+        // An empty message picks up the default
+        let blank_id = client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, ""));
+        assert_eq!(client.get_tip(&blank_id).message, default);
+
+        // A provided message is kept as-is
+        let custom = String::from_str(&env, "Great stream");
+        let custom_id = client.send_tip(&sender, &creator, &token, &100, &custom);
+        assert_eq!(client.get_tip(&custom_id).message, custom);
+    }
+
+    #[test]
+    fn test_verify_tip_hash() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let first = client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, "a"));
+        let second = client.send_tip(&sender, &creator, &token, &200, &String::from_str(&env, "b"));
+
+        let receipt = client.get_tip_hash(&first);
+        assert!(client.verify_tip_hash(&first, &receipt));
+
+        // A receipt for one tip does not verify another
+        assert!(!client.verify_tip_hash(&second, &receipt));
+        assert!(!client.verify_tip_hash(&first, &BytesN::from_array(&env, &[0; 32])));
+    }
+
+    #[test]
+    fn test_withdraw_multi() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token_a, token_a_client, token_a_admin) = create_token(&env);
+        let (token_b, token_b_client, token_b_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_a_admin.mint(&sender, &1_000);
+        token_b_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        client.send_tip(&sender, &creator, &token_a, &300, &empty);
+        client.send_tip(&sender, &creator, &token_b, &500, &empty);
+
+        // Overdrawing the second token rolls back the first withdrawal too
+        let tokens = Vec::from_array(&env, [token_a.clone(), token_b.clone()]);
+        let too_much = Vec::from_array(&env, [100, 600]);
+        assert!(client.try_withdraw_multi(&creator, &tokens, &too_much).is_err());
+        assert_eq!(client.get_balance(&creator, &token_a).available, 300);
+        assert_eq!(token_a_client.balance(&creator), 0);
+
+        let amounts = Vec::from_array(&env, [100, 500]);
+        client.withdraw_multi(&creator, &tokens, &amounts);
+        assert_eq!(client.get_balance(&creator, &token_a).available, 200);
+        assert_eq!(client.get_balance(&creator, &token_b).available, 0);
+        assert_eq!(token_a_client.balance(&creator), 100);
+        assert_eq!(token_b_client.balance(&creator), 500);
+    }
+
+    #[test]
+    fn test_get_tip_count_for_user() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let other = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        token_admin.mint(&other, &1_000);
+        let empty = String::from_str(&env, "");
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        client.send_tip(&other, &creator, &token, &100, &empty);
+
+        assert_eq!(client.get_tip_count_for_user(&creator), 3);
+        assert_eq!(
+            client.get_tip_count_for_user(&creator),
+            client.get_tips_for_user(&creator).len()
+        );
+        assert_eq!(client.get_tip_count_for_sender(&sender), 2);
+        assert_eq!(client.get_tip_count_for_sender(&creator), 0);
+    }
+
+    #[test]
+    fn test_feature_flag_blocks_batch() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        let recipients = Vec::from_array(&env, [alice.clone(), bob.clone()]);
+        let amounts = Vec::from_array(&env, [100, 200]);
+
+        // Features are on by default
+        assert!(client.is_feature_enabled(&symbol_short!("batch")));
+        let ids = client.send_tips_batch(&sender, &token, &recipients, &amounts, &empty);
+        assert_eq!(ids.len(), 2);
+        assert_eq!(client.get_balance(&bob, &token).available, 200);
+        assert_eq!(token_client.balance(&sender), 700);
+
+        // Disabling the batch feature blocks batches but not single tips
+        client.set_feature_flag(&admin, &symbol_short!("batch"), &false, &symbol_short!("none"));
+        assert!(!client.is_feature_enabled(&symbol_short!("batch")));
+        assert!(client.try_send_tips_batch(&sender, &token, &recipients, &amounts, &empty).is_err());
+        client.send_tip(&sender, &alice, &token, &50, &empty);
+        assert_eq!(client.get_balance(&alice, &token).available, 150);
+    }
+
+    #[test]
+    fn test_allow_self_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&creator, &1_000);
+        let empty = String::from_str(&env, "");
+
+        // Self-tips are rejected by default
+        assert!(!client.is_self_tip_allowed());
+        assert!(client.try_send_tip(&creator, &creator, &token, &100, &empty).is_err());
+
+        client.set_allow_self_tip(&admin, &true, &symbol_short!("none"));
+        client.send_tip(&creator, &creator, &token, &100, &empty);
+        assert_eq!(client.get_balance(&creator, &token).available, 100);
+
+        client.set_allow_self_tip(&admin, &false, &symbol_short!("none"));
+        assert!(client.try_send_tip(&creator, &creator, &token, &100, &empty).is_err());
+    }
+
+    #[test]
+    fn test_balance_alert_fires_once() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        client.set_balance_alert(&creator, &token, &250);
+
+        // Counts alert events published so far
+        let alert_topics: Vec<soroban_sdk::Val> =
+            (Symbol::new(&env, "threshold_reached"), creator.clone()).into_val(&env);
+        let alerts = || {
+            env.events()
+                .all()
+                .iter()
+                .filter(|(_, topics, _)| *topics == alert_topics)
+                .count()
+        };
+
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        assert_eq!(alerts(), 0);
+        client.send_tip(&sender, &creator, &token, &200, &empty);
+        assert_eq!(alerts(), 1);
+
+        // Further tips above the threshold stay quiet until the alert is re-armed
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        assert_eq!(alerts(), 1);
+        assert!(client.get_balance_alert(&creator, &token).unwrap().triggered);
+    }
+
+    #[test]
+    fn test_version() {
+        let env = Env::default();
+        let (contract_id, client, _) = setup_contract(&env);
+
+        assert_eq!(client.version(), VERSION);
+
+        // The layout version is recorded at init for later migrations
+        let stored: u32 = env.as_contract(&contract_id, || {
+            env.storage().instance().get(&ConfigKey::Version).unwrap()
+        });
+        assert_eq!(stored, VERSION);
+    }
+
+    #[test]
+    fn test_migrate_tips_from_legacy_layout() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let none = symbol_short!("none");
+
+        // Seed the version 1 layout: every tip in one instance vector
+        env.as_contract(&contract_id, || {
+            let mut legacy = Vec::new(&env);
+            for (amount, timestamp) in [(100, 10u64), (250, 20u64), (75, 30u64)] {
+                legacy.push_back(LegacyTip {
+                    from: sender.clone(),
+                    to: creator.clone(),
+                    amount,
+                    message: String::from_str(&env, "old"),
+                    timestamp,
+                    token: token.clone(),
+                });
+            }
+            env.storage().instance().set(&Symbol::new(&env, "tip"), &legacy);
+        });
+
+        // The first batch takes the lowest ids, and new tips wait for the rest
+        assert_eq!(client.migrate_tips(&admin, &0, &2, &none), 2);
+        assert_eq!(client.get_total_tips_count(), 2);
+        assert_eq!(client.get_tip(&2).amount, 250);
+        let empty = String::from_str(&env, "");
+        assert!(client.try_send_tip(&sender, &creator, &token, &10, &empty).is_err());
+
+        // Batches must follow on from each other
+        assert!(client.try_migrate_tips(&admin, &0, &2, &none).is_err());
+        assert_eq!(client.migrate_tips(&admin, &2, &2, &none), 1);
+        assert_eq!(client.get_total_tips_count(), 3);
+        let third = client.get_tip(&3);
+        assert_eq!(third.amount, 75);
+        assert_eq!(third.timestamp, 30);
+        assert_eq!(client.get_tips_for_user(&creator).len(), 3);
+        assert_eq!(client.get_tip_count_for_sender(&sender), 3);
+
+        // New tips follow the legacy ones
+        assert_eq!(client.send_tip(&sender, &creator, &token, &10, &empty), 4);
+
+        // The legacy vector is gone, so running again changes nothing
+        assert_eq!(client.migrate_tips(&admin, &3, &2, &none), 0);
+        assert_eq!(client.get_total_tips_count(), 4);
+        env.as_contract(&contract_id, || {
+            assert!(!env.storage().instance().has(&Symbol::new(&env, "tip")));
+        });
+    }
+
+    #[test]
+    fn test_get_tips_by_category() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        let donation = Symbol::new(&env, "donation");
+        let bounty = symbol_short!("bounty");
+
+        client.send_tip_tagged(&sender, &creator, &token, &100, &empty, &donation);
+        client.send_tip_tagged(&sender, &creator, &token, &200, &empty, &bounty);
+        client.send_tip_tagged(&sender, &creator, &token, &300, &empty, &donation);
+        let plain_id = client.send_tip(&sender, &creator, &token, &50, &empty);
+
+        let donations = client.get_tips_by_category(&creator, &donation, &0, &10);
+        assert_eq!(donations.len(), 2);
+        assert_eq!(donations.get_unchecked(0).amount, 100);
+        assert_eq!(donations.get_unchecked(1).amount, 300);
+
+        let bounties = client.get_tips_by_category(&creator, &bounty, &0, &10);
+        assert_eq!(bounties.len(), 1);
+        assert_eq!(bounties.get_unchecked(0).amount, 200);
+
+        // Untagged tips fall under `none`
+        assert_eq!(client.get_tip(&plain_id).category, symbol_short!("none"));
+        assert_eq!(client.get_tips_by_category(&creator, &symbol_short!("none"), &0, &10).len(), 1);
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        env.ledger().set_timestamp(1_000);
+        client.set_rate_limit(&admin, &2, &60, &symbol_short!("none"));
+
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+
+        // The third tip inside the window is rejected
+        assert!(client.try_send_tip(&sender, &creator, &token, &10, &empty).is_err());
+
+        // Once the window rolls over the sender can tip again
+        env.ledger().set_timestamp(1_060);
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+        assert_eq!(client.get_tip_count_for_sender(&sender), 3);
+    }
+
+    #[test]
+    fn test_get_withdrawals_for_user() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let other = Address::generate(&env);
+        let cold_wallet = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        client.send_tip(&sender, &creator, &token, &500, &empty);
+        client.send_tip(&sender, &other, &token, &100, &empty);
+
+        env.ledger().set_timestamp(100);
+        client.withdraw(&creator, &token, &150);
+        client.withdraw(&other, &token, &100);
+        env.ledger().set_timestamp(200);
+        client.withdraw_to(&creator, &token, &250, &cold_wallet);
+
+        let history = client.get_withdrawals_for_user(&creator, &0, &10);
+        assert_eq!(history.len(), 2);
+        let first = history.get_unchecked(0);
+        assert_eq!((first.amount, first.timestamp, first.destination), (150, 100, creator.clone()));
+        let second = history.get_unchecked(1);
+        assert_eq!((second.amount, second.timestamp, second.destination), (250, 200, cold_wallet));
+
+        // Paging skips earlier entries
+        assert_eq!(client.get_withdrawals_for_user(&creator, &1, &10).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Token transfer amount mismatch")]
+    fn test_withdraw_rejects_short_transfer() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let user = Address::generate(&env);
+        let token = env.register_contract(None, ShortToken);
+        ShortTokenClient::new(&env, &token).mint(&contract_id, &100);
+
+        // Give the user a balance backed by the contract's holding
+        env.as_contract(&contract_id, || {
+            MicrotipContract::update_balance(&env, &user, &token, 100, true);
+        });
+
+        // The token only moves half, so the withdrawal must revert
+        client.withdraw(&user, &token, &100);
+    }
+
+    #[test]
+    fn test_tip_matching() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sponsor = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sponsor, &1_000);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        // The whole cap is escrowed and counted as owed
+        client.create_match(&sponsor, &creator, &token, &150);
+        assert_eq!(token_client.balance(&sponsor), 850);
+        assert_eq!(client.get_solvency(&token), (150, 150));
+
+        // Fully matched
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        assert_eq!(client.get_balance(&creator, &token).available, 200);
+        assert_eq!(client.get_match(&creator, &token).unwrap().remaining, 50);
+
+        // Partially matched at the cap, which exhausts the pledge
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        assert_eq!(client.get_balance(&creator, &token).available, 350);
+        assert!(client.get_match(&creator, &token).is_none());
+
+        // Unmatched once exhausted
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        assert_eq!(client.get_balance(&creator, &token).available, 450);
+        assert_eq!(token_client.balance(&contract_id), 450);
+        assert_eq!(client.get_solvency(&token), (450, 450));
+    }
+
+    #[test]
+    fn test_tip_event_topics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        env.ledger().set_timestamp(500);
+
+        client.send_tip_anonymous(&sender, &creator, &token, &100, &String::from_str(&env, ""));
+
+        // Topics carry the event name, token and recipient, in that order
+        let (_, topics, data) = env.events().all().last().unwrap();
+        assert_eq!(topics.len(), 3);
+        let name: Symbol = topics.get_unchecked(0).into_val(&env);
+        let topic_token: Address = topics.get_unchecked(1).into_val(&env);
+        let topic_to: Address = topics.get_unchecked(2).into_val(&env);
+        assert_eq!(name, symbol_short!("tip"));
+        assert_eq!(topic_token, token);
+        assert_eq!(topic_to, creator);
+
+        // The payload follows the event sequence number, with the sender still masked
+        let payload: (u64, Address, Address, i128, u64) = data.into_val(&env);
+        assert_eq!(payload.1, Address::from_string(&String::from_str(&env, ANONYMOUS_SENDER)));
+        assert_eq!((payload.2, payload.3, payload.4), (creator, 100, 500));
+    }
+
+    #[test]
+    fn test_get_tips_in_range() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        for (timestamp, amount) in [(100u64, 10i128), (200, 20), (300, 30)] {
+            env.ledger().set_timestamp(timestamp);
+            client.send_tip(&sender, &creator, &token, &amount, &empty);
+        }
+
+        let middle = client.get_tips_in_range(&creator, &150, &250, &0, &10);
+        assert_eq!(middle.len(), 1);
+        assert_eq!(middle.get_unchecked(0).amount, 20);
+
+        // Bounds are inclusive
+        assert_eq!(client.get_tips_in_range(&creator, &100, &300, &0, &10).len(), 3);
+        assert!(client.try_get_tips_in_range(&creator, &300, &100, &0, &10).is_err());
+    }
+
+    #[test]
+    fn test_withdrawal_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.send_tip(&sender, &creator, &token, &1_000, &String::from_str(&env, ""));
+
+        assert!(client.try_set_withdrawal_fee_bps(&admin, &1_001, &symbol_short!("none")).is_err());
+        client.set_withdrawal_fee_bps(&admin, &250, &symbol_short!("none"));
+
+        // 2.5% of each withdrawal stays behind as platform fees
+        client.withdraw(&creator, &token, &400);
+        client.withdraw(&creator, &token, &200);
+        assert_eq!(token_client.balance(&creator), 390 + 195);
+        assert_eq!(client.get_balance(&creator, &token).available, 400);
+        assert_eq!(client.reconcile(&token), (415, 15));
+        assert_eq!(token_client.balance(&contract_id), 415);
+    }
+
+    #[test]
+    fn test_goal_reached() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let quick = Address::generate(&env);
+        let steady = Address::generate(&env);
+        token_admin.mint(&sender, &2_000);
+        let empty = String::from_str(&env, "");
+        let goal_topics = |user: &Address| -> Vec<soroban_sdk::Val> {
+            (Symbol::new(&env, "goal_reached"), user.clone()).into_val(&env)
+        };
+        let reached = |user: &Address| {
+            let topics = goal_topics(user);
+            env.events().all().iter().filter(|(_, t, _)| *t == topics).count()
+        };
+
+        // Crossed in a single tip
+        client.set_goal(&quick, &token, &500);
+        client.send_tip(&sender, &quick, &token, &600, &empty);
+        assert_eq!(reached(&quick), 1);
+        assert_eq!(client.get_goal_progress(&quick, &token), (600, 500));
+
+        // Crossed across several tips, firing only on the crossing one
+        client.set_goal(&steady, &token, &300);
+        client.send_tip(&sender, &steady, &token, &200, &empty);
+        assert_eq!(reached(&steady), 0);
+        assert_eq!(client.get_goal_progress(&steady, &token), (200, 300));
+        client.send_tip(&sender, &steady, &token, &100, &empty);
+        assert_eq!(reached(&steady), 1);
+        client.send_tip(&sender, &steady, &token, &100, &empty);
+        assert_eq!(reached(&steady), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid token")]
+    fn test_send_tip_rejects_non_token_address() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let not_a_token = Address::generate(&env);
+
+        client.send_tip(&sender, &creator, &not_a_token, &100, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    fn test_close_account() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let (other_token, _, other_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        other_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        client.send_tip(&sender, &creator, &other_token, &100, &empty);
+
+        // Refused while funds remain
+        assert!(client.try_close_account(&creator, &token).is_err());
+
+        client.withdraw(&creator, &token, &100);
+        client.close_account(&creator, &token);
+
+        // Only the other token's balance is left
+        let balances = client.get_all_balances_for_user(&creator);
+        assert_eq!(balances.len(), 1);
+        assert_eq!(balances.get_unchecked(0).token, other_token);
+        assert_eq!(client.get_balance(&creator, &token).total_received, 0);
+    }
+
+    #[test]
+    fn test_pool_shares() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let carol = Address::generate(&env);
+        let outsider = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        let members = Vec::from_array(&env, [alice.clone(), bob.clone(), carol.clone()]);
+        let pool_id = client.create_pool(&alice, &members, &token);
+        client.tip_to_pool(&sender, &pool_id, &100, &empty);
+        assert_eq!(client.get_solvency(&token), (100, 100));
+
+        // 100 splits as 34/33/33, with the remainder going to the first member
+        assert_eq!(client.claim_pool_share(&alice, &pool_id), 34);
+        assert_eq!(client.claim_pool_share(&bob, &pool_id), 33);
+        assert_eq!(client.claim_pool_share(&carol, &pool_id), 33);
+        assert_eq!(token_client.balance(&contract_id), 0);
+        assert!(client.try_claim_pool_share(&alice, &pool_id).is_err());
+        assert!(client.try_claim_pool_share(&outsider, &pool_id).is_err());
+
+        // At 102 everyone is owed 34, so only bob and carol have more to claim
+        client.tip_to_pool(&sender, &pool_id, &2, &empty);
+        assert!(client.try_claim_pool_share(&alice, &pool_id).is_err());
+        assert_eq!(client.claim_pool_share(&bob, &pool_id), 1);
+        assert_eq!(client.claim_pool_share(&carol, &pool_id), 1);
+        assert_eq!(token_client.balance(&alice), 34);
+        assert_eq!(token_client.balance(&bob), 34);
+        assert_eq!(token_client.balance(&carol), 34);
+        assert_eq!(client.get_solvency(&token), (0, 0));
+    }
+
+    #[test]
+    fn test_find_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let tip_id = client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, ""));
+
+        assert_eq!(client.find_tip(&tip_id).unwrap().amount, 100);
+        assert!(client.find_tip(&(tip_id + 1)).is_none());
+        assert!(client.find_tip(&0).is_none());
+    }
+
+    #[test]
+    fn test_multisig_withdraw_fees() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let (fee_token, fee_client, fee_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        fee_admin.mint(&sender, &100);
+        client.set_fee_config(&admin, &fee_token, &5, &symbol_short!("none"));
+        client.send_tip(&sender, &creator, &token, &400, &String::from_str(&env, ""));
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let carol = Address::generate(&env);
+        let admins = Vec::from_array(&env, [alice.clone(), bob.clone(), carol.clone()]);
+        client.set_admins(&admin, &admins, &2, &symbol_short!("none"));
+
+        // The single-key path is closed once an admin set exists
+        assert!(client.try_withdraw_fees(&admin, &fee_token, &treasury, &symbol_short!("none")).is_err());
+
+        // One approval is not enough
+        let action = Action::WithdrawFees(fee_token.clone(), treasury.clone());
+        let proposal_id = client.propose_action(&alice, &action, &symbol_short!("none"));
+        assert!(!client.get_proposal(&proposal_id).executed);
+        assert_eq!(fee_client.balance(&treasury), 0);
+        assert!(client.try_approve_action(&alice, &proposal_id, &symbol_short!("none")).is_err());
+
+        // A second distinct admin reaches the 2-of-3 threshold
+        assert!(client.approve_action(&carol, &proposal_id, &symbol_short!("none")));
+        assert_eq!(fee_client.balance(&treasury), 5);
+        assert_eq!(client.reconcile(&fee_token), (0, 0));
+        assert!(client.try_approve_action(&bob, &proposal_id, &symbol_short!("none")).is_err());
+    }
+
+    #[test]
+    fn test_send_tip_with_balance() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        client.send_tip(&sender, &creator, &token, &150, &empty);
+
+        let result = client.send_tip_with_balance(&sender, &creator, &token, &100, &empty);
+        assert_eq!(result.tip_id, 2);
+        assert_eq!(result.recipient_available, 250);
+        assert_eq!(
+            result.recipient_available,
+            client.get_balance(&creator, &token).available
+        );
+    }
+
+    #[test]
+    fn test_fee_exempt_sender() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let (fee_token, fee_client, fee_admin) = create_token(&env);
+        let partner = Address::generate(&env);
+        let regular = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&partner, &1_000);
+        token_admin.mint(&regular, &1_000);
+        fee_admin.mint(&partner, &100);
+        fee_admin.mint(&regular, &100);
+        let empty = String::from_str(&env, "");
+        client.set_fee_config(&admin, &fee_token, &5, &symbol_short!("none"));
+        client.set_fee_exempt(&admin, &partner, &true, &symbol_short!("none"));
+        assert!(client.is_fee_exempt(&partner));
+        assert!(!client.is_fee_exempt(&regular));
+
+        client.send_tip(&partner, &creator, &token, &100, &empty);
+        client.send_tip(&regular, &creator, &token, &100, &empty);
+
+        assert_eq!(fee_client.balance(&partner), 100);
+        assert_eq!(fee_client.balance(&regular), 95);
+        assert_eq!(client.reconcile(&fee_token), (5, 5));
+    }
+
+    #[test]
+    fn test_get_fee_stats() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let (fee_token, _, fee_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        fee_admin.mint(&sender, &100);
+        let empty = String::from_str(&env, "");
+        client.set_fee_config(&admin, &fee_token, &5, &symbol_short!("none"));
+
+        for _ in 0..3 {
+            client.send_tip(&sender, &creator, &token, &100, &empty);
+        }
+        assert_eq!(client.get_fee_stats(&fee_token), (15, 15));
+
+        // Withdrawing empties the bucket but not the lifetime figure
+        client.withdraw_fees(&admin, &fee_token, &treasury, &symbol_short!("none"));
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        assert_eq!(client.get_fee_stats(&fee_token), (20, 5));
+    }
+
+    #[test]
+    fn test_edit_tip_message() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        env.ledger().set_timestamp(1_000);
+        let tip_id = client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, "grat work"));
+
+        // Within the default 300 second window the sender can fix the typo
+        env.ledger().set_timestamp(1_300);
+        let fixed = String::from_str(&env, "great work");
+        client.edit_tip_message(&sender, &tip_id, &fixed);
+        assert_eq!(client.get_tip(&tip_id).message, fixed);
+
+        // Only the sender may edit
+        assert!(client.try_edit_tip_message(&creator, &tip_id, &fixed).is_err());
+
+        // After the window closes edits are rejected
+        env.ledger().set_timestamp(1_301);
+        let late = String::from_str(&env, "late");
+        assert!(client.try_edit_tip_message(&sender, &tip_id, &late).is_err());
+        assert_eq!(client.get_tip(&tip_id).message, fixed);
+    }
+
+    #[test]
+    fn test_send_tip_idempotent() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let other = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        token_admin.mint(&other, &1_000);
+        let message = String::from_str(&env, "");
+        let key = BytesN::from_array(&env, &[7; 32]);
+
+        let first = client.send_tip_idempotent(&sender, &creator, &token, &100, &message, &key);
+
+        // The retry is a no-op that hands back the original id
+        let retry = client.send_tip_idempotent(&sender, &creator, &token, &100, &message, &key);
+        assert_eq!(retry, first);
+        assert_eq!(client.get_total_tips_count(), 1);
+        assert_eq!(token_client.balance(&sender), 900);
+        assert_eq!(client.get_balance(&creator, &token).available, 100);
+
+        // Another sender cannot reuse the key, and a fresh key sends a new tip
+        assert!(client
+            .try_send_tip_idempotent(&other, &creator, &token, &100, &message, &key)
+            .is_err());
+        let fresh = BytesN::from_array(&env, &[8; 32]);
+        let second = client.send_tip_idempotent(&sender, &creator, &token, &100, &message, &fresh);
+        assert_ne!(second, first);
+    }
+
+    #[test]
+    fn test_hashed_tip_ids() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let message = String::from_str(&env, "");
+        let key = BytesN::from_array(&env, &[7; 32]);
+        env.ledger().set_timestamp(1_000);
+        client.set_hashed_tip_ids(&admin, &true, &symbol_short!("none"));
+
+        // The id is derived from the tip and kept apart from counter ids
+        let first = client.send_tip_idempotent(&sender, &creator, &token, &100, &message, &key);
+        let expected = env.as_contract(&contract_id, || {
+            MicrotipContract::hashed_tip_id(&env, &sender, &creator, 1_000, 1)
+        });
+        assert_eq!(first, expected);
+        assert_ne!(first & HASHED_ID_FLAG, 0);
+        assert_eq!(client.get_tip(&first).amount, 100);
+
+        // A client retry with the same idempotency key is detected
+        let retry = client.send_tip_idempotent(&sender, &creator, &token, &100, &message, &key);
+        assert_eq!(retry, first);
+        assert_eq!(client.get_total_tips_count(), 1);
+        assert_eq!(token_client.balance(&sender), 900);
+
+        // Feeds and claim tracking still follow the order tips were sent in
+        let second = client.send_tip(&sender, &creator, &token, &50, &message);
+        let recent = client.get_recent_tips(&10);
+        assert_eq!((recent.get_unchecked(0).amount, recent.get_unchecked(1).amount), (50, 100));
+        client.set_claim_window(&admin, &100, &symbol_short!("none"));
+        client.withdraw(&creator, &token, &1);
+        env.ledger().set_timestamp(1_100);
+        assert!(client.try_reclaim_expired_tip(&sender, &second).is_err());
+
+        // Anonymous tips keep counter ids so the id cannot reveal the sender
+        let anonymous = client.send_tip_anonymous(&sender, &creator, &token, &10, &message);
+        assert_eq!(anonymous, 3);
+
+        // Switching the mode off goes back to counter ids
+        client.set_hashed_tip_ids(&admin, &false, &symbol_short!("none"));
+        assert_eq!(client.send_tip(&sender, &creator, &token, &10, &message), 4);
+    }
+
+    #[test]
+    fn test_hashed_tip_id_collision_falls_back_to_counter() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let message = String::from_str(&env, "");
+        client.set_hashed_tip_ids(&admin, &true, &symbol_short!("none"));
+
+        // Occupy the id the next tip would hash to
+        let timestamp = env.ledger().timestamp();
+        env.as_contract(&contract_id, || {
+            let taken = MicrotipContract::hashed_tip_id(&env, &sender, &creator, timestamp, 1);
+            let placeholder = MicrotipContract::new_tip(
+                &env,
+                sender.clone(),
+                creator.clone(),
+                token.clone(),
+                1,
+                String::from_str(&env, ""),
+            );
+            env.storage().persistent().set(&TipKey::Tip(taken), &placeholder);
+        });
+
+        let tip_id = client.send_tip(&sender, &creator, &token, &100, &message);
+        assert_eq!(tip_id, 1);
+        assert_eq!(client.get_tip(&1).amount, 100);
+    }
+
+    #[test]
+    fn test_withdraw_cooldown() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.send_tip(&sender, &creator, &token, &500, &String::from_str(&env, ""));
+        client.set_withdraw_cooldown(&admin, &3_600, &symbol_short!("none"));
+
+        env.ledger().set_timestamp(10_000);
+        client.withdraw(&creator, &token, &100);
+
+        // A second withdrawal inside the hour is blocked
+        env.ledger().set_timestamp(13_599);
+        assert!(client.try_withdraw(&creator, &token, &100).is_err());
+
+        env.ledger().set_timestamp(13_600);
+        client.withdraw(&creator, &token, &100);
+        assert_eq!(token_client.balance(&creator), 200);
+    }
+
+    #[test]
+    fn test_get_token_leaderboard() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token_a, _, token_a_admin) = create_token(&env);
+        let (token_b, _, token_b_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_a_admin.mint(&sender, &1_000);
+        token_b_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        client.send_tip(&sender, &creator, &token_a, &300, &empty);
+        client.send_tip(&sender, &creator, &token_b, &200, &empty);
+        client.send_tip(&sender, &creator, &token_b, &250, &empty);
+
+        // token_b overtakes token_a after its second tip
+        let board = client.get_token_leaderboard(&10);
+        assert_eq!(board.len(), 2);
+        assert_eq!(board.get_unchecked(0), (token_b.clone(), 450));
+        assert_eq!(board.get_unchecked(1), (token_a.clone(), 300));
+        assert_eq!(client.get_token_leaderboard(&1).len(), 1);
+    }
+
+    #[test]
+    fn test_refund_tip_partial() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let tip_id = client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, ""));
+
+        client.refund_tip_partial(&creator, &tip_id, &100);
+        assert_eq!(token_client.balance(&sender), 800);
+        assert_eq!(client.get_balance(&creator, &token).available, 200);
+
+        // Refunding more than what is left is rejected
+        assert!(client.try_refund_tip_partial(&creator, &tip_id, &201).is_err());
+
+        // The second part brings the total to the full tip
+        client.refund_tip_partial(&creator, &tip_id, &200);
+        assert_eq!(token_client.balance(&sender), 1_000);
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+        assert!(client.try_refund_tip_partial(&creator, &tip_id, &1).is_err());
+        assert!(client.try_refund_tip(&creator, &tip_id).is_err());
+    }
+
+    #[test]
+    fn test_tip_back() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let fan = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let bystander = Address::generate(&env);
+        token_admin.mint(&fan, &1_000);
+        token_admin.mint(&creator, &100);
+        let empty = String::from_str(&env, "");
+        let tip_id = client.send_tip(&fan, &creator, &token, &300, &empty);
+
+        let back_id = client.tip_back(&creator, &tip_id, &50, &String::from_str(&env, "thank you!"));
+        let back = client.get_tip(&back_id);
+        assert_eq!(back.from, creator);
+        assert_eq!(back.to, fan);
+        assert_eq!(back.token, token);
+        assert_eq!(client.get_balance(&fan, &token).available, 50);
+        assert_eq!(token_client.balance(&creator), 50);
+
+        // Only the recipient of the original tip may tip back
+        assert!(client.try_tip_back(&bystander, &tip_id, &50, &empty).is_err());
+        assert!(client.try_tip_back(&creator, &99, &50, &empty).is_err());
+    }
+
+    #[test]
+    fn test_send_tips_batch_lenient() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        // A zero amount and a self-tip are skipped; the rest go through
+        let recipients = Vec::from_array(&env, [alice.clone(), bob.clone(), sender.clone(), bob.clone()]);
+        let amounts = Vec::from_array(&env, [100, 0, 50, 200]);
+        let results =
+            client.send_tips_batch_lenient(&sender, &token, &recipients, &amounts, &String::from_str(&env, ""));
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results.get_unchecked(0), Some(1));
+        assert_eq!(results.get_unchecked(1), None);
+        assert_eq!(results.get_unchecked(2), None);
+        assert_eq!(results.get_unchecked(3), Some(2));
+        assert_eq!(token_client.balance(&sender), 700);
+        assert_eq!(client.get_balance(&alice, &token).available, 100);
+        assert_eq!(client.get_balance(&bob, &token).available, 200);
+    }
+
+    #[test]
+    fn test_get_total_available() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token_a, _, token_a_admin) = create_token(&env);
+        let (token_b, _, token_b_admin) = create_token(&env);
+        let (token_c, _, _) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_a_admin.mint(&sender, &1_000);
+        token_b_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        client.send_tip(&sender, &creator, &token_a, &120, &empty);
+        client.send_tip(&sender, &creator, &token_b, &80, &empty);
+
+        let tokens = Vec::from_array(&env, [token_a.clone(), token_c.clone(), token_b.clone()]);
+        let available = client.get_total_available(&creator, &tokens);
+        assert_eq!(available.len(), 2);
+        assert_eq!(available.get_unchecked(0), (token_a, 120));
+        assert_eq!(available.get_unchecked(1), (token_b, 80));
+    }
+
+    #[test]
+    fn test_private_tip_hidden_from_recent_feed() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        let public_id = client.send_tip(&sender, &creator, &token, &100, &empty);
+        let private_id = client.send_tip_private(&sender, &creator, &token, &200, &empty);
+
+        // The recipient is credited for both
+        assert_eq!(client.get_balance(&creator, &token).available, 300);
+        assert!(client.get_tip(&public_id).public);
+        assert!(!client.get_tip(&private_id).public);
+
+        // Only the public tip shows up in the feed
+        let recent = client.get_recent_tips(&10);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent.get_unchecked(0).amount, 100);
+    }
+
+    #[test]
+    fn test_fund_own_jar() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&creator, &1_000);
+
+        client.fund_own_jar(&creator, &token, &250);
+
+        assert_eq!(client.get_balance(&creator, &token).available, 250);
+        assert_eq!(token_client.balance(&contract_id), 250);
+        assert_eq!(client.get_solvency(&token), (250, 250));
+
+        // No tip was recorded
+        assert_eq!(client.get_total_tips_count(), 0);
+        assert_eq!(client.get_tip_count_for_user(&creator), 0);
+        assert!(client.find_tip(&1).is_none());
+    }
+
+    #[test]
+    fn test_get_tips_since() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        env.ledger().set_timestamp(100);
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+        client.send_tip(&sender, &creator, &token, &20, &empty);
+        env.ledger().set_timestamp(500);
+        client.send_tip(&sender, &creator, &token, &30, &empty);
+        client.send_tip(&sender, &creator, &token, &40, &empty);
+
+        let recent = client.get_tips_since(&500, &10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent.get_unchecked(0).amount, 40);
+        assert_eq!(recent.get_unchecked(1).amount, 30);
+        assert_eq!(client.get_tips_since(&0, &10).len(), 4);
+        assert_eq!(client.get_tips_since(&501, &10).len(), 0);
+    }
+
+    #[test]
+    fn test_require_registration() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        client.set_require_registration(&admin, &true, &symbol_short!("none"));
+
+        // Unregistered recipients cannot be tipped
+        assert!(!client.is_registered(&creator));
+        assert!(client.try_send_tip(&sender, &creator, &token, &100, &empty).is_err());
+
+        client.register_user(&admin, &creator, &symbol_short!("none"));
+        assert!(client.is_registered(&creator));
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        assert_eq!(client.get_balance(&creator, &token).available, 100);
+    }
+
+    #[test]
+    fn test_withdraw_as_delegate() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let manager = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, ""));
+        client.set_withdraw_delegate(&creator, &manager);
+
+        // The manager triggers the withdrawal, but the creator gets the funds
+        client.withdraw_as_delegate(&manager, &creator, &token, &200);
+        assert_eq!(token_client.balance(&creator), 200);
+        assert_eq!(token_client.balance(&manager), 0);
+        assert_eq!(client.get_balance(&creator, &token).available, 100);
+
+        assert!(client.try_withdraw_as_delegate(&stranger, &creator, &token, &50).is_err());
+    }
+
+    #[test]
+    fn test_pause_token() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (broken, _, broken_admin) = create_token(&env);
+        let (healthy, _, healthy_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        broken_admin.mint(&sender, &1_000);
+        healthy_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        client.pause_token(&admin, &broken, &symbol_short!("none"));
+        assert!(client.is_token_paused(&broken));
+        assert!(client.try_send_tip(&sender, &creator, &broken, &100, &empty).is_err());
+
+        // Other tokens are unaffected
+        client.send_tip(&sender, &creator, &healthy, &100, &empty);
+        assert_eq!(client.get_balance(&creator, &healthy).available, 100);
+
+        client.unpause_token(&admin, &broken, &symbol_short!("none"));
+        client.send_tip(&sender, &creator, &broken, &100, &empty);
+        assert_eq!(client.get_balance(&creator, &broken).available, 100);
+    }
+
+    #[test]
+    fn test_get_top_pairs() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let artist = Address::generate(&env);
+        token_admin.mint(&alice, &1_000);
+        token_admin.mint(&bob, &1_000);
+        let empty = String::from_str(&env, "");
+
+        client.send_tip(&alice, &creator, &token, &100, &empty);
+        client.send_tip(&bob, &artist, &token, &250, &empty);
+        client.send_tip(&alice, &artist, &token, &50, &empty);
+        client.send_tip(&alice, &creator, &token, &200, &empty);
+
+        // alice -> creator climbs to the top with its second tip
+        let pairs = client.get_top_pairs(&10);
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs.get_unchecked(0), (alice.clone(), creator.clone(), 300));
+        assert_eq!(pairs.get_unchecked(1), (bob.clone(), artist.clone(), 250));
+        assert_eq!(pairs.get_unchecked(2), (alice.clone(), artist.clone(), 50));
+        assert_eq!(client.get_top_pairs(&2).len(), 2);
+    }
+
+    #[test]
+    fn test_unsend_last_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        env.ledger().set_timestamp(1_000);
+        let tip_id = client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, "oops"));
+
+        env.ledger().set_timestamp(1_060);
+        assert_eq!(client.unsend_last_tip(&sender), tip_id);
+        assert_eq!(token_client.balance(&sender), 1_000);
+        let balance = client.get_balance(&creator, &token);
+        assert_eq!(balance.available, 0);
+        assert_eq!(balance.total_received, 0);
+        assert_eq!(client.get_user_profile(&sender).tips_sent, 0);
+        assert_eq!(client.get_user_profile(&creator).total_received, 0);
+
+        // The same tip cannot be unsent twice or refunded afterwards
+        assert!(client.try_unsend_last_tip(&sender).is_err());
+        assert!(client.try_refund_tip(&creator, &tip_id).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsend window has expired")]
+    fn test_unsend_after_window_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        env.ledger().set_timestamp(1_000);
+        client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, ""));
+
+        env.ledger().set_timestamp(1_061);
+        client.unsend_last_tip(&sender);
+    }
+
+    #[test]
+    fn test_unsend_after_withdrawal_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, ""));
+        client.withdraw(&creator, &token, &100);
+        assert!(client.try_unsend_last_tip(&sender).is_err());
+    }
+
+    #[test]
+    fn test_preview_tip_matches_actual_split() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let (fee_token, fee_client, fee_admin) = create_token(&env);
+        let partner = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        fee_admin.mint(&sender, &100);
+        client.set_fee_config(&admin, &fee_token, &5, &symbol_short!("none"));
+        client.set_fee_exempt(&admin, &partner, &true, &symbol_short!("none"));
+
+        let (net, fee) = client.preview_tip(&token, &200, &Some(sender.clone()));
+        assert_eq!((net, fee), (200, 5));
+        assert_eq!(client.preview_tip(&token, &200, &None), (200, 5));
+        assert_eq!(client.preview_tip(&token, &200, &Some(partner)), (200, 0));
+
+        client.send_tip(&sender, &creator, &token, &200, &String::from_str(&env, ""));
+        assert_eq!(client.get_balance(&creator, &token).available, net);
+        assert_eq!(100 - fee_client.balance(&sender), fee);
+    }
+
+    #[test]
+    fn test_prune_refunded_tips() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        let refunded_id = client.send_tip(&sender, &creator, &token, &100, &empty);
+        let kept_id = client.send_tip(&sender, &creator, &token, &200, &empty);
+
+        // Tips that still hold funds cannot be pruned
+        assert!(client
+            .try_prune_refunded_tips(&admin, &Vec::from_array(&env, [kept_id]), &symbol_short!("none"))
+            .is_err());
+
+        client.refund_tip(&creator, &refunded_id);
+        client.prune_refunded_tips(&admin, &Vec::from_array(&env, [refunded_id]), &symbol_short!("none"));
+
+        assert!(client.find_tip(&refunded_id).is_none());
+        assert_eq!(client.get_tip_count_for_user(&creator), 1);
+        assert_eq!(client.get_tip_count_for_sender(&sender), 1);
+        assert_eq!(client.get_tips_for_user(&creator).get_unchecked(0).amount, 200);
+        assert_eq!(client.get_recent_tips(&10).len(), 1);
+    }
+
+    #[test]
+    fn test_send_tip_default() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_default_token(&admin, &token, &symbol_short!("none"));
+        assert_eq!(client.get_default_token(), Some(token.clone()));
+
+        let tip_id = client.send_tip_default(&sender, &creator, &150, &String::from_str(&env, "hi"));
+        assert_eq!(client.get_tip(&tip_id).token, token);
+        assert_eq!(client.get_balance(&creator, &token).available, 150);
+    }
+
+    #[test]
+    #[should_panic(expected = "No default token configured")]
+    fn test_send_tip_default_unconfigured_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        client.send_tip_default(&sender, &creator, &150, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    fn test_tip_streak() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        // Two tips on day 1 count once, then one each on days 2 and 3
+        env.ledger().set_timestamp(86_400 + 100);
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+        assert_eq!(client.get_user_profile(&sender).streak, 1);
+        env.ledger().set_timestamp(2 * 86_400 + 50_000);
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+        env.ledger().set_timestamp(3 * 86_400 + 10);
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+        assert_eq!(client.get_user_profile(&sender).streak, 3);
+
+        // Skipping day 4 breaks the streak
+        env.ledger().set_timestamp(5 * 86_400);
+        client.send_tip(&sender, &creator, &token, &10, &empty);
+        let profile = client.get_user_profile(&sender);
+        assert_eq!(profile.streak, 1);
+        assert_eq!(profile.streak_updated, 5 * 86_400);
+        assert_eq!(client.get_user_profile(&creator).streak, 0);
+    }
+
+    #[test]
+    fn test_get_tips_for_user_min() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &10_000);
+        let empty = String::from_str(&env, "");
+        for amount in [5, 500, 50, 1_000, 499] {
+            client.send_tip(&sender, &creator, &token, &amount, &empty);
+        }
+
+        let tips = client.get_tips_for_user_min(&creator, &500, &0, &10);
+        assert_eq!(tips.len(), 2);
+        assert_eq!(tips.get_unchecked(0).amount, 500);
+        assert_eq!(tips.get_unchecked(1).amount, 1_000);
+
+        // Pagination counts only qualifying tips
+        let page = client.get_tips_for_user_min(&creator, &50, &1, &2);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page.get_unchecked(0).amount, 50);
+        assert_eq!(page.get_unchecked(1).amount, 1_000);
+    }
+
+    #[test]
+    fn test_withdraw_all_fees() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let (fee_token, fee_client, fee_admin) = create_token(&env);
+        let (idle_token, _, _) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        fee_admin.mint(&sender, &100);
+
+        // Flat tip fees accrue in one token, withdrawal fees in the other
+        client.set_fee_config(&admin, &fee_token, &5, &symbol_short!("none"));
+        client.set_withdrawal_fee_bps(&admin, &100, &symbol_short!("none"));
+        client.send_tip(&sender, &creator, &token, &1_000, &String::from_str(&env, ""));
+        client.withdraw(&creator, &token, &1_000);
+
+        let tokens = Vec::from_array(&env, [token.clone(), idle_token, fee_token.clone()]);
+        let paid = client.withdraw_all_fees(&admin, &tokens, &treasury, &symbol_short!("none"));
+        assert_eq!(paid.len(), 2);
+        assert_eq!(paid.get_unchecked(0), (token.clone(), 10));
+        assert_eq!(paid.get_unchecked(1), (fee_token.clone(), 5));
+        assert_eq!(token_client.balance(&treasury), 10);
+        assert_eq!(fee_client.balance(&treasury), 5);
+        assert_eq!(client.get_fee_stats(&token), (10, 0));
+        assert_eq!(client.get_fee_stats(&fee_token), (5, 0));
+    }
+
+    #[test]
+    fn test_max_batch() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        token_admin.mint(&sender, &10_000);
+        let empty = String::from_str(&env, "");
+        assert_eq!(client.get_limits().max_batch, 50);
+
+        // One past the default limit is rejected
+        let mut recipients = Vec::new(&env);
+        let mut amounts = Vec::new(&env);
+        for _ in 0..51 {
+            recipients.push_back(Address::generate(&env));
+            amounts.push_back(10i128);
+        }
+        assert!(client.try_send_tips_batch(&sender, &token, &recipients, &amounts, &empty).is_err());
+
+        // Exactly at a configured limit is accepted, one more is not
+        client.set_max_batch(&admin, &3, &symbol_short!("none"));
+        let at_limit = recipients.slice(0..3);
+        let amounts_at_limit = amounts.slice(0..3);
+        assert_eq!(
+            client.send_tips_batch(&sender, &token, &at_limit, &amounts_at_limit, &empty).len(),
+            3
+        );
+        let above = recipients.slice(0..4);
+        let amounts_above = amounts.slice(0..4);
+        assert!(client.try_send_tips_batch(&sender, &token, &above, &amounts_above, &empty).is_err());
+        assert!(client
+            .try_send_tips_batch_lenient(&sender, &token, &above, &amounts_above, &empty)
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Batch limit exceeds ceiling")]
+    fn test_max_batch_ceiling() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        client.set_max_batch(&admin, &201, &symbol_short!("none"));
+    }
+
+    #[test]
+    fn test_get_receipt() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        env.ledger().set_timestamp(4_242);
+        let tip_id = client.send_tip(&sender, &creator, &token, &120, &String::from_str(&env, "gg"));
+
+        let tip = client.get_tip(&tip_id);
+        let receipt = client.get_receipt(&tip_id);
+        assert_eq!(receipt.tip_id, tip_id);
+        assert_eq!(receipt.from, tip.from);
+        assert_eq!(receipt.to, tip.to);
+        assert_eq!(receipt.amount, tip.amount);
+        assert_eq!(receipt.message, tip.message);
+        assert_eq!(receipt.timestamp, 4_242);
+        assert_eq!(receipt.token, token);
+        assert_eq!(receipt.contract, contract_id);
+        assert_eq!(receipt.version, client.version());
+        assert!(client.verify_tip_hash(&tip_id, &receipt.hash));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot tip the contract")]
+    fn test_tip_to_contract_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.send_tip(&sender, &contract_id, &token, &100, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    fn test_batch_to_contract_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+        let recipients = Vec::from_array(&env, [creator, contract_id]);
+        let amounts = Vec::from_array(&env, [100i128, 100]);
+
+        assert!(client.try_send_tips_batch(&sender, &token, &recipients, &amounts, &empty).is_err());
+        let results = client.send_tips_batch_lenient(&sender, &token, &recipients, &amounts, &empty);
+        assert!(results.get_unchecked(0).is_some());
+        assert!(results.get_unchecked(1).is_none());
+    }
+
+    #[test]
+    fn test_emergency_withdraw_reserves_user_funds() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let rescue = Address::generate(&env);
+        token_admin.mint(&sender, &2_000);
+        client.set_fee_config(&admin, &token, &10, &symbol_short!("none"));
+        client.send_tip(&sender, &creator, &token, &600, &String::from_str(&env, ""));
+
+        // The creator's balance and the accrued fee are both reserved
+        client.pause(&admin, &symbol_short!("none"));
+        assert_eq!(client.emergency_withdraw(&admin, &token, &rescue, &symbol_short!("none")), 0);
+        assert_eq!(token_client.balance(&contract_id), 610);
+        assert_eq!(client.get_fee_stats(&token), (10, 10));
+
+        // Only a surplus beyond everything owed can be taken
+        token_admin.mint(&contract_id, &25);
+        assert_eq!(client.emergency_withdraw(&admin, &token, &rescue, &symbol_short!("none")), 25);
+        assert_eq!(client.get_solvency(&token), (610, 610));
+
+        client.unpause(&admin, &symbol_short!("none"));
+        client.withdraw(&creator, &token, &600);
+        assert_eq!(token_client.balance(&creator), 600);
+    }
+
+    #[test]
+    fn test_get_rank() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let (other_token, _, _) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let carol = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        token_admin.mint(&sender, &10_000);
+        let empty = String::from_str(&env, "");
+
+        client.send_tip(&sender, &alice, &token, &300, &empty);
+        client.send_tip(&sender, &bob, &token, &500, &empty);
+        client.send_tip(&sender, &carol, &token, &100, &empty);
+        assert_eq!(client.get_rank(&bob, &token), Some(1));
+        assert_eq!(client.get_rank(&alice, &token), Some(2));
+        assert_eq!(client.get_rank(&carol, &token), Some(3));
+
+        // Carol overtakes both with a large tip
+        client.send_tip(&sender, &carol, &token, &450, &empty);
+        assert_eq!(client.get_rank(&carol, &token), Some(1));
+        assert_eq!(client.get_rank(&bob, &token), Some(2));
+        assert_eq!(client.get_rank(&alice, &token), Some(3));
+        assert_eq!(
+            client.get_leaderboard(&token, &1).get_unchecked(0),
+            (carol.clone(), 550)
+        );
+
+        assert_eq!(client.get_rank(&stranger, &token), None);
+        assert_eq!(client.get_rank(&bob, &other_token), None);
+    }
+
+    #[test]
+    fn test_release_conditional_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_oracle(&admin, &oracle, &symbol_short!("none"));
+
+        let id = client.create_conditional_tip(&sender, &creator, &token, &400, &symbol_short!("stream"));
+        assert_eq!(token_client.balance(&sender), 600);
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+        assert_eq!(client.get_solvency(&token), (400, 400));
+
+        // Only the admin or the oracle can settle it
+        assert!(client.try_release_conditional_tip(&creator, &id).is_err());
+        let tip_id = client.release_conditional_tip(&oracle, &id);
+        assert_eq!(client.get_tip(&tip_id).amount, 400);
+        assert_eq!(client.get_balance(&creator, &token).available, 400);
+        assert!(client.get_conditional_tip(&id).is_none());
+        assert!(client.try_release_conditional_tip(&oracle, &id).is_err());
+    }
+
+    #[test]
+    fn test_cancel_conditional_tip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+
+        let id = client.create_conditional_tip(&sender, &creator, &token, &400, &symbol_short!("stream"));
+        assert!(client.get_conditional_tip(&id).is_some());
+        client.cancel_conditional_tip(&admin, &id);
+
+        assert_eq!(token_client.balance(&sender), 1_000);
+        assert_eq!(client.get_solvency(&token), (0, 0));
+        assert_eq!(client.get_tip_count_for_user(&creator), 0);
+        assert!(client.try_release_conditional_tip(&admin, &id).is_err());
+    }
+
+    #[test]
+    fn test_sweep_dormant() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_dormancy(&admin, &1_000, &symbol_short!("none"));
+
+        env.ledger().set_timestamp(5_000);
+        client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, ""));
+
+        env.ledger().set_timestamp(6_000);
+        assert_eq!(client.sweep_dormant(&admin, &creator, &token, &symbol_short!("none")), 300);
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+        assert_eq!(client.get_charity_pool(&token), 300);
+        assert_eq!(client.get_solvency(&token), (300, 300));
+
+        assert_eq!(client.withdraw_charity_pool(&admin, &token, &treasury, &symbol_short!("none")), 300);
+        assert_eq!(token_client.balance(&treasury), 300);
+        assert_eq!(client.get_charity_pool(&token), 0);
+    }
+
+    #[test]
+    fn test_sweep_active_account_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_dormancy(&admin, &1_000, &symbol_short!("none"));
+
+        env.ledger().set_timestamp(5_000);
+        client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, ""));
+
+        // A withdrawal counts as activity too
+        env.ledger().set_timestamp(5_500);
+        client.withdraw(&creator, &token, &100);
+        env.ledger().set_timestamp(6_000);
+        assert!(client.try_sweep_dormant(&admin, &creator, &token, &symbol_short!("none")).is_err());
+        assert_eq!(client.get_balance(&creator, &token).available, 200);
+
+        env.ledger().set_timestamp(6_500);
+        assert_eq!(client.sweep_dormant(&admin, &creator, &token, &symbol_short!("none")), 200);
+    }
+
+    #[test]
+    fn test_sweep_tracks_activity_per_balance() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let (other_token, _, other_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let legacy_user = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        token_admin.mint(&creator, &1_000);
+        other_admin.mint(&sender, &1_000);
+        client.set_dormancy(&admin, &1_000, &symbol_short!("none"));
+
+        env.ledger().set_timestamp(5_000);
+        client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, ""));
+        client.send_tip(&sender, &creator, &other_token, &300, &String::from_str(&env, ""));
+
+        // Topping up the jar keeps that balance active, but not the other token's
+        env.ledger().set_timestamp(5_500);
+        client.fund_own_jar(&creator, &token, &50);
+        env.ledger().set_timestamp(6_000);
+        assert!(client.try_sweep_dormant(&admin, &creator, &token, &symbol_short!("none")).is_err());
+        assert_eq!(client.sweep_dormant(&admin, &creator, &other_token, &symbol_short!("none")), 300);
+
+        // A balance with no recorded activity counts as dormant
+        env.as_contract(&contract_id, || {
+            env.storage().instance().set(
+                &AccountKey::Balance(legacy_user.clone(), token.clone()),
+                &Balance {
+                    total_received: 40,
+                    available: 40,
+                    withdrawn: 0,
+                    token: token.clone(),
+                },
+            );
+        });
+        assert_eq!(client.sweep_dormant(&admin, &legacy_user, &token, &symbol_short!("none")), 40);
+    }
+
+    #[test]
+    fn test_get_tip_full() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let tip_id = client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, "ty"));
+
+        let status = client.get_tip_full(&tip_id);
+        assert!(!status.refunded);
+        assert_eq!(status.refunded_amount, 0);
+        assert_eq!(status.ack, None);
+
+        client.refund_tip_partial(&creator, &tip_id, &120);
+        client.acknowledge_tip(&creator, &tip_id, &symbol_short!("thanks"));
+        let status = client.get_tip_full(&tip_id);
+        assert_eq!(status.tip.amount, 300);
+        assert_eq!(status.tip.from, sender);
+        assert!(!status.refunded);
+        assert_eq!(status.refunded_amount, 120);
+        assert_eq!(status.ack, Some(symbol_short!("thanks")));
+
+        client.refund_tip(&creator, &tip_id);
+        let status = client.get_tip_full(&tip_id);
+        assert!(status.refunded);
+        assert_eq!(status.refunded_amount, 300);
+    }
+
+    #[test]
+    fn test_receive_cap() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &10_000);
+        let empty = String::from_str(&env, "");
+        client.set_receive_cap(&creator, &token, &500, &86_400);
+        assert_eq!(client.get_receive_cap(&creator, &token), Some((500, 86_400)));
+
+        // Tips up to the cap are accepted within the window
+        env.ledger().set_timestamp(1_000);
+        client.send_tip(&sender, &creator, &token, &300, &empty);
+        client.send_tip(&sender, &creator, &token, &200, &empty);
+        assert!(client.try_send_tip(&sender, &creator, &token, &1, &empty).is_err());
+
+        // A new window starts from zero
+        env.ledger().set_timestamp(1_000 + 86_400);
+        client.send_tip(&sender, &creator, &token, &450, &empty);
+        assert_eq!(client.get_balance(&creator, &token).available, 950);
+    }
+
+    #[test]
+    #[should_panic(expected = "recipient cap exceeded")]
+    fn test_receive_cap_exceeded_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &10_000);
+        client.set_receive_cap(&creator, &token, &500, &3_600);
+        client.send_tip(&sender, &creator, &token, &501, &String::from_str(&env, ""));
+    }
+
+    #[test]
+    fn test_event_seq_increases() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        assert_eq!(client.get_event_seq(), 0);
+
+        // Each call ends with its own event, numbered after the previous one
+        let tip_id = client.send_tip(&sender, &creator, &token, &300, &String::from_str(&env, ""));
+        let (_, _, data) = env.events().all().last().unwrap();
+        let (tip_seq, ..): (u64, Address, Address, i128, u64) = data.into_val(&env);
+
+        client.refund_tip_partial(&creator, &tip_id, &100);
+        let (_, _, data) = env.events().all().last().unwrap();
+        let (refund_seq, ..): (u64, u64, Address, i128, u64) = data.into_val(&env);
+
+        client.withdraw(&creator, &token, &200);
+        let (_, _, data) = env.events().all().last().unwrap();
+        let (withdrawal_seq, ..): (u64, Address, Address, i128, u64) = data.into_val(&env);
+
+        assert_eq!((tip_seq, refund_seq, withdrawal_seq), (1, 2, 3));
+        assert_eq!(client.get_event_seq(), 3);
+    }
+
+    #[test]
+    fn test_admin_set_changes_need_approval() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let carol = Address::generate(&env);
+        client.set_admins(&admin, &Vec::from_array(&env, [alice.clone(), bob.clone()]), &2, &symbol_short!("none"));
+
+        // The original admin can no longer replace the set on their own
+        let takeover = Vec::from_array(&env, [admin.clone()]);
+        assert!(client.try_set_admins(&admin, &takeover, &1, &symbol_short!("none")).is_err());
+
+        // Invalid sets are rejected before anyone approves them
+        let invalid = Action::SetAdmins(Vec::from_array(&env, [carol.clone()]), 2);
+        assert!(client.try_propose_action(&alice, &invalid, &symbol_short!("none")).is_err());
+
+        let action = Action::SetAdmins(Vec::from_array(&env, [alice.clone(), carol.clone()]), 1);
+        let proposal_id = client.propose_action(&alice, &action, &symbol_short!("none"));
+        assert_eq!(client.get_admins().1, 2);
+        assert!(client.approve_action(&bob, &proposal_id, &symbol_short!("none")));
+        assert_eq!(
+            client.get_admins(),
+            (Vec::from_array(&env, [alice, carol]), 1)
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_counts_every_path() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let carol = Address::generate(&env);
+        token_admin.mint(&sender, &10_000);
+        let empty = String::from_str(&env, "");
+        env.ledger().set_timestamp(1_000);
+        client.set_rate_limit(&admin, &3, &60, &symbol_short!("none"));
+
+        // A batch of three tips uses up the whole window
+        let recipients = Vec::from_array(&env, [alice.clone(), bob.clone(), carol.clone()]);
+        let amounts = Vec::from_array(&env, [10i128, 10, 10]);
+        client.send_tips_batch(&sender, &token, &recipients, &amounts, &empty);
+        assert!(client.try_send_tip(&sender, &alice, &token, &10, &empty).is_err());
+        let members = Vec::from_array(&env, [alice.clone(), bob.clone()]);
+        let pool_id = client.create_pool(&alice, &members, &token);
+        assert!(client.try_tip_to_pool(&sender, &pool_id, &10, &empty).is_err());
+        assert!(client
+            .try_create_conditional_tip(&sender, &alice, &token, &10, &symbol_short!("stream"))
+            .is_err());
+
+        // A split larger than the window is rejected outright
+        env.ledger().set_timestamp(1_060);
+        let shares = Vec::from_array(&env, [1u32, 1, 1, 1]);
+        let four = Vec::from_array(&env, [alice.clone(), bob.clone(), carol.clone(), Address::generate(&env)]);
+        assert!(client.try_send_split_tip(&sender, &token, &40, &four, &shares, &empty).is_err());
+        client.send_split_tip(&sender, &token, &30, &recipients, &shares.slice(0..3), &empty);
+        assert!(client.try_tip_to_pool(&sender, &pool_id, &10, &empty).is_err());
+    }
+
+    #[test]
+    fn test_receive_cap_lenient_batch_skips_over_cap() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_receive_cap(&creator, &token, &500, &3_600);
+
+        // The second entry would take the batch's running total past the cap
+        let recipients = Vec::from_array(&env, [creator.clone(), creator.clone(), creator.clone()]);
+        let amounts = Vec::from_array(&env, [300, 300, 200]);
+        let results =
+            client.send_tips_batch_lenient(&sender, &token, &recipients, &amounts, &String::from_str(&env, ""));
+
+        assert_eq!(results.get_unchecked(0), Some(1));
+        assert_eq!(results.get_unchecked(1), None);
+        assert_eq!(results.get_unchecked(2), Some(2));
+        assert_eq!(token_client.balance(&sender), 500);
+        assert_eq!(client.get_balance(&creator, &token).available, 500);
+    }
+
+    #[test]
+    fn test_receive_cap_strict_batch_counts_running_total() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_receive_cap(&creator, &token, &500, &3_600);
+
+        let recipients = Vec::from_array(&env, [creator.clone(), creator.clone()]);
+        let amounts = Vec::from_array(&env, [300, 300]);
+        let result =
+            client.try_send_tips_batch(&sender, &token, &recipients, &amounts, &String::from_str(&env, ""));
+        assert!(result.is_err());
+        assert_eq!(token_client.balance(&sender), 1_000);
+    }
+
+    #[test]
+    fn test_receive_cap_conditional_counted_at_creation() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        client.set_oracle(&admin, &oracle, &symbol_short!("none"));
+        client.set_receive_cap(&creator, &token, &500, &3_600);
+
+        // The escrowed amount uses up the cap, so a direct tip is rejected
+        let id = client.create_conditional_tip(&sender, &creator, &token, &400, &symbol_short!("stream"));
+        let empty = String::from_str(&env, "");
+        assert!(client.try_send_tip(&sender, &creator, &token, &200, &empty).is_err());
+
+        // Release is not blocked, even after the cap is lowered beneath it
+        client.set_receive_cap(&creator, &token, &100, &3_600);
+        client.release_conditional_tip(&oracle, &id);
+        assert_eq!(client.get_balance(&creator, &token).available, 400);
+
+        // A cancelled conditional tip frees its share of the cap
+        client.set_receive_cap(&creator, &token, &500, &3_600);
+        let id = client.create_conditional_tip(&sender, &creator, &token, &100, &symbol_short!("stream"));
+        client.cancel_conditional_tip(&oracle, &id);
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+    }
+
+    #[test]
+    fn test_cancel_match_returns_remaining() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sponsor = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sponsor, &1_000);
+        token_admin.mint(&sender, &1_000);
+        client.create_match(&sponsor, &creator, &token, &300);
+        client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, ""));
+
+        // Only the sponsor can pull the unused part of the pledge
+        assert!(client.try_cancel_match(&sender, &creator, &token).is_err());
+        assert_eq!(client.cancel_match(&sponsor, &creator, &token), 200);
+        assert_eq!(token_client.balance(&sponsor), 900);
+        assert!(client.get_match(&creator, &token).is_none());
+        assert_eq!(token_client.balance(&contract_id), 200);
+        assert_eq!(client.get_solvency(&token), (200, 200));
+        assert!(client.try_cancel_match(&sponsor, &creator, &token).is_err());
+    }
+
+    #[test]
+    fn test_unsend_reverses_match() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sponsor = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sponsor, &1_000);
+        token_admin.mint(&sender, &1_000);
+        client.create_match(&sponsor, &creator, &token, &300);
+        client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, ""));
+
+        // The match goes back into the still-active pledge
+        client.unsend_last_tip(&sender);
+        let balance = client.get_balance(&creator, &token);
+        assert_eq!((balance.available, balance.total_received), (0, 0));
+        assert_eq!(client.get_match(&creator, &token).unwrap().remaining, 300);
+        assert_eq!(token_client.balance(&contract_id), 300);
+        assert_eq!(client.get_solvency(&token), (300, 300));
+    }
+
+    #[test]
+    fn test_refund_reverses_match() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sponsor = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sponsor, &1_000);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        // The first tip exhausts the pledge, so its match is repaid to the sponsor
+        client.create_match(&sponsor, &creator, &token, &100);
+        let tip_id = client.send_tip(&sender, &creator, &token, &100, &empty);
+        assert!(client.get_match(&creator, &token).is_none());
+        client.refund_tip(&creator, &tip_id);
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+        assert_eq!(token_client.balance(&sponsor), 1_000);
+        assert_eq!(token_client.balance(&sender), 1_000);
+        assert_eq!(token_client.balance(&contract_id), 0);
+        assert_eq!(client.get_solvency(&token), (0, 0));
+    }
+
+    #[test]
+    fn test_partial_refund_reverses_match_share() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, _) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sponsor = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sponsor, &1_000);
+        token_admin.mint(&sender, &1_000);
+        client.create_match(&sponsor, &creator, &token, &500);
+        let tip_id = client.send_tip(&sender, &creator, &token, &200, &String::from_str(&env, ""));
+
+        // Refunding a quarter of the tip takes back a quarter of its match
+        client.refund_tip_partial(&creator, &tip_id, &50);
+        assert_eq!(client.get_balance(&creator, &token).available, 300);
+        assert_eq!(client.get_match(&creator, &token).unwrap().remaining, 350);
+
+        // Refunding the rest takes back whatever is left of the match
+        client.refund_tip(&creator, &tip_id);
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+        assert_eq!(client.get_match(&creator, &token).unwrap().remaining, 500);
+        assert_eq!(token_client.balance(&sender), 1_000);
+        assert_eq!(token_client.balance(&contract_id), 500);
+        assert_eq!(client.get_solvency(&token), (500, 500));
+    }
+
+    #[test]
+    fn test_reclaim_reverses_match() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (contract_id, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let sponsor = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sponsor, &1_000);
+        token_admin.mint(&sender, &1_000);
+        client.set_claim_window(&admin, &100, &symbol_short!("none"));
+        client.create_match(&sponsor, &creator, &token, &300);
+        let tip_id = client.send_tip(&sender, &creator, &token, &100, &String::from_str(&env, ""));
+
+        // A cancelled pledge cannot take the match back, so the sponsor is repaid directly
+        assert_eq!(client.cancel_match(&sponsor, &creator, &token), 200);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 100);
+        client.reclaim_expired_tip(&sender, &tip_id);
+        assert_eq!(client.get_balance(&creator, &token).available, 0);
+        assert_eq!(token_client.balance(&sponsor), 1_000);
+        assert_eq!(token_client.balance(&sender), 1_000);
+        assert_eq!(token_client.balance(&contract_id), 0);
+    }
+
+    #[test]
+    fn test_match_applies_to_batch_and_conditional_tips() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sponsor = Address::generate(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let oracle = Address::generate(&env);
+        token_admin.mint(&sponsor, &1_000);
+        token_admin.mint(&sender, &1_000);
+        client.set_oracle(&admin, &oracle, &symbol_short!("none"));
+        client.create_match(&sponsor, &creator, &token, &500);
+
+        let recipients = Vec::from_array(&env, [creator.clone()]);
+        let amounts = Vec::from_array(&env, [100]);
+        client.send_tips_batch(&sender, &token, &recipients, &amounts, &String::from_str(&env, ""));
+        assert_eq!(client.get_balance(&creator, &token).available, 200);
+
+        // Conditional tips are matched when they are released
+        let id = client.create_conditional_tip(&sender, &creator, &token, &150, &symbol_short!("stream"));
+        assert_eq!(client.get_match(&creator, &token).unwrap().remaining, 400);
+        client.release_conditional_tip(&oracle, &id);
+        assert_eq!(client.get_balance(&creator, &token).available, 500);
+        assert_eq!(client.get_match(&creator, &token).unwrap().remaining, 250);
+    }
+
+    #[test]
+    fn test_private_tip_leaves_leaderboards_unchanged() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        token_admin.mint(&sender, &10_000);
+        let empty = String::from_str(&env, "");
+        client.send_tip(&sender, &alice, &token, &300, &empty);
+        client.send_tip(&sender, &bob, &token, &200, &empty);
+        let board = client.get_leaderboard(&token, &10);
+        let token_board = client.get_token_leaderboard(&10);
+
+        // A large private tip would otherwise move bob to the top
+        client.send_tip_private(&sender, &bob, &token, &1_000, &empty);
+        assert_eq!(client.get_leaderboard(&token, &10), board);
+        assert_eq!(client.get_token_leaderboard(&10), token_board);
+        assert_eq!(client.get_rank(&bob, &token), Some(2));
+
+        // Later public tips rank on the public total only
+        client.send_tip(&sender, &bob, &token, &50, &empty);
+        assert_eq!(client.get_leaderboard(&token, &1).get_unchecked(0), (alice, 300));
+        assert_eq!(client.get_token_leaderboard(&1).get_unchecked(0), (token, 550));
+    }
+
+    #[test]
+    fn test_fund_own_jar_skips_received_totals() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&creator, &1_000);
+        client.set_goal(&creator, &token, &100);
+        client.set_balance_alert(&creator, &token, &100);
+
+        // Only the spendable balance grows; goals and alerts are untouched
+        client.fund_own_jar(&creator, &token, &250);
+        let balance = client.get_balance(&creator, &token);
+        assert_eq!((balance.available, balance.total_received), (250, 0));
+        assert_eq!(client.get_goal_progress(&creator, &token), (0, 100));
+        assert!(!client.get_balance_alert(&creator, &token).unwrap().triggered);
+
+        // Paused tokens and non-token contracts are refused
+        client.pause_token(&admin, &token, &symbol_short!("none"));
+        assert!(client.try_fund_own_jar(&creator, &token, &100).is_err());
+        let not_a_token = Address::generate(&env);
+        assert!(client.try_fund_own_jar(&creator, &not_a_token, &100).is_err());
+    }
+
+    #[test]
+    fn test_unsend_rolls_back_derived_stats() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        let other = Address::generate(&env);
+        token_admin.mint(&sender, &10_000);
+        let empty = String::from_str(&env, "");
+        client.set_receive_cap(&creator, &token, &500, &3_600);
+        client.send_tip(&sender, &other, &token, &200, &empty);
+
+        env.ledger().set_timestamp(1_000);
+        client.send_tip(&sender, &creator, &token, &500, &empty);
+        assert_eq!(client.get_rank(&creator, &token), Some(1));
+        client.unsend_last_tip(&sender);
+
+        // Rankings, pair volume and retention no longer include the tip
+        assert_eq!(client.get_rank(&creator, &token), None);
+        assert_eq!(client.get_token_leaderboard(&1).get_unchecked(0), (token.clone(), 200));
+        assert_eq!(client.get_top_pairs(&10).len(), 1);
+        assert_eq!(client.get_retention(&creator), (0, 0));
+
+        // The recipient's receive window has room again
+        client.send_tip(&sender, &creator, &token, &500, &empty);
+
+        // Unique counts are not inflated by sending again after an unsend
+        let stats = client.get_platform_stats();
+        assert_eq!((stats.unique_senders, stats.unique_recipients), (1, 2));
+        assert_eq!(client.get_retention(&creator), (1, 0));
+    }
+
+    #[test]
+    fn test_unsend_first_tip_rolls_back_unique_counts() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        let stats = client.get_platform_stats();
+        assert_eq!((stats.unique_senders, stats.unique_recipients), (1, 1));
+
+        // Neither party has any other tip, so neither is counted any more
+        client.unsend_last_tip(&sender);
+        let stats = client.get_platform_stats();
+        assert_eq!((stats.unique_senders, stats.unique_recipients), (0, 0));
+
+        // Tipping again counts them once more
+        client.send_tip(&sender, &creator, &token, &100, &empty);
+        let stats = client.get_platform_stats();
+        assert_eq!((stats.unique_senders, stats.unique_recipients), (1, 1));
+    }
+
+    #[test]
+    fn test_tip_hash_unique_for_identical_tips() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (_, other_client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let message = String::from_str(&env, "same");
+
+        // Same parties, amount, message and timestamp
+        let first = client.send_tip(&sender, &creator, &token, &100, &message);
+        let second = client.send_tip(&sender, &creator, &token, &100, &message);
+        assert_ne!(client.get_tip_hash(&first), client.get_tip_hash(&second));
+
+        // The same tip id on another deployment has its own receipt
+        let other_first = other_client.send_tip(&sender, &creator, &token, &100, &message);
+        assert_eq!(other_first, first);
+        assert_ne!(other_client.get_tip_hash(&other_first), client.get_tip_hash(&first));
+    }
+
+    #[test]
+    fn test_tip_to_pool_uses_tip_validation() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let pool_id = client.create_pool(&alice, &Vec::from_array(&env, [alice.clone()]), &token);
+
+        // Small tips cannot carry a message, just like direct tips
+        client.set_message_min_amount(&admin, &50, &symbol_short!("none"));
+        assert!(client.try_tip_to_pool(&sender, &pool_id, &10, &String::from_str(&env, "hi")).is_err());
+        client.tip_to_pool(&sender, &pool_id, &10, &String::from_str(&env, ""));
+
+        // A paused token stops pool tips too
+        client.pause_token(&admin, &token, &symbol_short!("none"));
+        assert!(client.try_tip_to_pool(&sender, &pool_id, &100, &String::from_str(&env, "")).is_err());
+        assert_eq!(client.get_pool(&pool_id).total_received, 10);
+    }
+
+    #[test]
+    fn test_tip_to_pool_charges_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, admin) = setup_contract(&env);
+        let (token, token_client, token_admin) = create_token(&env);
+        let (fee_token, fee_client, fee_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let alice = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        fee_admin.mint(&sender, &100);
+        let pool_id = client.create_pool(&alice, &Vec::from_array(&env, [alice.clone()]), &token);
+
+        client.set_fee_config(&admin, &fee_token, &5, &symbol_short!("none"));
+        client.tip_to_pool(&sender, &pool_id, &300, &String::from_str(&env, ""));
+
+        // The fee is taken on top, and the pool receives the full tip
+        assert_eq!(fee_client.balance(&sender), 95);
+        assert_eq!(client.reconcile(&fee_token), (5, 5));
+        assert_eq!(token_client.balance(&sender), 700);
+        assert_eq!(client.get_pool(&pool_id).total_received, 300);
+
+        // Exempt senders skip the fee, as with direct tips
+        client.set_fee_exempt(&admin, &sender, &true, &symbol_short!("none"));
+        client.tip_to_pool(&sender, &pool_id, &100, &String::from_str(&env, ""));
+        assert_eq!(fee_client.balance(&sender), 95);
+    }
+
+    #[test]
+    fn test_anonymous_tip_skips_sender_stats() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (_, client, _) = setup_contract(&env);
+        let (token, _, token_admin) = create_token(&env);
+        let sender = Address::generate(&env);
+        let creator = Address::generate(&env);
+        token_admin.mint(&sender, &1_000);
+        let empty = String::from_str(&env, "");
+
+        client.send_tip_anonymous(&sender, &creator, &token, &250, &empty);
+
+        // Nothing public ties the tip back to its sender
+        let profile = client.get_user_profile(&sender);
+        assert_eq!((profile.tips_sent, profile.total_sent), (0, 0));
+        assert_eq!(client.get_tip_count_for_sender(&sender), 0);
+        assert_eq!(client.get_platform_stats().unique_senders, 0);
+        assert_eq!(client.get_user_profile(&creator).total_received, 250);
+
+        // Unsending it does not touch the sender's stats either
+        client.unsend_last_tip(&sender);
+        assert_eq!(client.get_user_profile(&sender).tips_sent, 0);
+        assert_eq!(client.get_user_profile(&creator).total_received, 0);
     }
 }